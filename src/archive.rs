@@ -0,0 +1,203 @@
+use zip::{result::ZipError, ZipArchive};
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read, Seek},
+    path::Path,
+};
+
+use crate::{error::ParseError, FileName, Mdff};
+
+const MAX_SIZE_HINT: u64 = 1 << 24;
+
+pub type EntryResult<'a> = Result<Mdff<'a>,ParseError>;
+
+/// A single file extracted from a ZIP archive.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Entry {
+    name: String,
+    contents: String,
+}
+
+impl Entry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    /// Parses the entry as a NEM12 or NEM13 file. See [`crate::parse`].
+    pub fn parse(&self) -> EntryResult<'_> {
+        crate::parse(&self.contents)
+    }
+}
+
+/// The file entries of a ZIP archive, read into memory.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Archive {
+    entries: Vec<Entry>,
+}
+
+impl Archive {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Archive,ArchiveError> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    /// Reads the CSV entries of an archive, skipping directories and any other
+    /// files, such as a readme. Entries named as MDFF files, such as
+    /// `NEM12#ID#FROM#TO.V01` or one with no extension, are read as CSV too.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Archive,ArchiveError> {
+        let mut zip = ZipArchive::new(reader)?;
+        let mut entries = Vec::with_capacity(zip.len());
+
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i)?;
+            if file.is_dir() || !is_csv(file.name()) {
+                continue;
+            }
+
+            // The declared size is only a hint, so don't let it drive a huge allocation.
+            let mut buf = Vec::with_capacity(file.size().min(MAX_SIZE_HINT) as usize);
+            file.read_to_end(&mut buf)?;
+
+            let contents = String::from_utf8(buf)
+                .map_err(|e| ArchiveError::InvalidUtf8 { name: file.name().to_owned(), error: e })?;
+            entries.push(Entry {
+                name: file.name().to_owned(),
+                contents,
+            });
+        }
+
+        Ok(Archive { entries })
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Parses every entry in the archive, pairing each result with the entry name.
+    pub fn parse(&self) -> Vec<(&str,EntryResult<'_>)> {
+        self.entries.iter()
+            .map(|entry| (entry.name(),entry.parse()))
+            .collect()
+    }
+}
+
+/// Whether an entry holds an MDFF file: one with a `.csv` extension in any
+/// case, or named as an MDFF file.
+fn is_csv(name: &str) -> bool {
+    let name = name.rsplit('/').next().unwrap_or(name);
+    name.to_ascii_lowercase().ends_with(".csv") || FileName::parse(name).is_ok()
+}
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    Zip(ZipError),
+    /// An entry whose contents aren't valid UTF-8.
+    InvalidUtf8 { name: String, error: std::string::FromUtf8Error },
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Io(e) => write!(f, "failed to read archive: {}", e),
+            ArchiveError::Zip(e) => write!(f, "invalid archive: {}", e),
+            ArchiveError::InvalidUtf8 { name, error } => write!(f, "entry {} is not valid UTF-8: {}", name, error),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArchiveError::Io(e) => Some(e),
+            ArchiveError::Zip(e) => Some(e),
+            ArchiveError::InvalidUtf8 { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(e: io::Error) -> Self {
+        ArchiveError::Io(e)
+    }
+}
+
+impl From<ZipError> for ArchiveError {
+    fn from(e: ZipError) -> Self {
+        match e {
+            ZipError::Io(e) => ArchiveError::Io(e),
+            e => ArchiveError::Zip(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_nem12_archive() {
+        let archive = Archive::open("examples/nem12/NEM12#SCENARIO605033001#ENERGEXM#NEMMCO.zip").unwrap();
+        assert_eq!(archive.entries().len(),1);
+        assert!(archive.entries()[0].contents().starts_with("100,NEM12,"));
+
+        let results = archive.parse();
+        assert_eq!(results[0].0,archive.entries()[0].name());
+    }
+
+    #[test]
+    fn open_uppercase_extension() {
+        let archive = Archive::open("examples/nem12/NEM12#NEM1201005Scenario1#GLOBALM#NEMMCO.ZIP").unwrap();
+        assert!(!archive.entries().is_empty());
+    }
+
+    #[test]
+    fn open_nem13_archive() {
+        let archive = Archive::open("examples/nem13/NEM13#Scenario18#ETSAMDP#NEMMCO.zip").unwrap();
+        assert!(archive.entries()[0].contents().starts_with("100,NEM13,"));
+    }
+
+    fn zip_of(files: &[(&str,&[u8])]) -> io::Cursor<Vec<u8>> {
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        for (name,contents) in files {
+            zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            io::Write::write_all(&mut zip, contents).unwrap();
+        }
+        let mut cursor = zip.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn skips_other_files() {
+        let contents: &[u8] = b"100,NEM12,200404201300,MDA1,Ret1\n900\n";
+        let archive = Archive::from_reader(zip_of(&[
+            ("readme.txt",b"not an MDFF file"),
+            ("data/first.CSV",contents),
+            ("NEM12#0123456789#MDA1#Ret1.V01",contents),
+            ("logo.png",&[0x89,b'P',b'N',b'G']),
+        ])).unwrap();
+        let names = archive.entries().iter().map(Entry::name).collect::<Vec<_>>();
+        assert_eq!(names,["data/first.CSV","NEM12#0123456789#MDA1#Ret1.V01"]);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let res = Archive::from_reader(zip_of(&[("first.csv",b"100,NEM12,200404201300,MDA1,Ret\xff\n900\n")]));
+        match res {
+            Err(err @ ArchiveError::InvalidUtf8 { .. }) => assert!(err.to_string().starts_with("entry first.csv is not valid UTF-8")),
+            res => panic!("{:?}",res),
+        }
+    }
+
+    #[test]
+    fn invalid_archive() {
+        let res = Archive::from_reader(io::Cursor::new(b"100,NEM12,200404201300,MDA1,Ret1\n900\n"));
+        assert!(matches!(res,Err(ArchiveError::Zip(_))));
+    }
+}
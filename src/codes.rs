@@ -1,90 +1,351 @@
 #![allow(non_snake_case)]
 #![allow(non_upper_case_globals)]
-#![allow(dead_code)]
+
+use std::{fmt, str};
+
+/// The error returned when a string is not a known code.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct CodeError {
+    kind: &'static str,
+    value: String,
+}
+
+impl CodeError {
+    pub(crate) fn new(kind: &'static str, value: &str) -> Self {
+        CodeError { kind, value: value.to_owned() }
+    }
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown {} '{}'", self.kind, self.value)
+    }
+}
+
+impl std::error::Error for CodeError { }
+
+/// Implements `TryFrom<&str>` in terms of `FromStr`.
+macro_rules! try_from_str {
+    ($($name:ident),*) => {
+        $(impl TryFrom<&str> for $name {
+            type Error = CodeError;
+
+            fn try_from(s: &str) -> Result<Self,Self::Error> {
+                s.parse()
+            }
+        })*
+    };
+}
+
+/// Implements `Serialize` and `Deserialize` as the code's text in the file,
+/// in terms of `Display` and `FromStr`.
+#[cfg(feature = "serde")]
+macro_rules! serde_str {
+    ($($name:ident),*) => {
+        $(impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl <'de>serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        })*
+    };
+}
+
+/// Declares an enum of string codes, each linked to an entry in one of the
+/// metadata modules below.
+macro_rules! code_enum {
+    (
+        $(#[$attr:meta])*
+        $name:ident, $accessor:ident -> $meta:ty {
+            $($variant:ident = $code:literal => $value:expr,)*
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $code,)*
+                }
+            }
+
+            pub fn $accessor(&self) -> $meta {
+                match self {
+                    $($name::$variant => $value,)*
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
 
 pub mod TRANSACTION_CODE {
-    pub const A: &'static str = "Alteration";
-    pub const C: &'static str = "Meter Reconfiguration";
-    pub const G: &'static str = "Re-energisation";
-    pub const D: &'static str = "De-energisation";
-    pub const E: &'static str = "Forward Estimate";
-    pub const N: &'static str = "Normal Read";
-    pub const O: &'static str = "Other";
-    pub const S: &'static str = "Special Read";
-    pub const R: &'static str = "Removal of Meter";
+    pub const A: &str = "Alteration";
+    pub const C: &str = "Meter Reconfiguration";
+    pub const G: &str = "Re-energisation";
+    pub const D: &str = "De-energisation";
+    pub const E: &str = "Forward Estimate";
+    pub const N: &str = "Normal Read";
+    pub const O: &str = "Other";
+    pub const S: &str = "Special Read";
+    pub const R: &str = "Removal of Meter";
+}
+
+code_enum! {
+    /// The transaction code of a 500 or 550 record. See [`TRANSACTION_CODE`] for descriptions.
+    TransactionCode, description -> &'static str {
+        A = "A" => TRANSACTION_CODE::A,
+        C = "C" => TRANSACTION_CODE::C,
+        G = "G" => TRANSACTION_CODE::G,
+        D = "D" => TRANSACTION_CODE::D,
+        E = "E" => TRANSACTION_CODE::E,
+        N = "N" => TRANSACTION_CODE::N,
+        O = "O" => TRANSACTION_CODE::O,
+        S = "S" => TRANSACTION_CODE::S,
+        R = "R" => TRANSACTION_CODE::R,
+    }
+}
+
+impl str::FromStr for TransactionCode {
+    type Err = CodeError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        TransactionCode::ALL.iter().copied().find(|code| code.as_str() == s)
+            .ok_or_else(|| CodeError::new("transaction code", s))
+    }
 }
 
 pub mod UOM {
-    pub const MWh: &'static UomMeta   = &UomMeta { name: "Megawatt Hour", multiplier: 1e6 as f64 };
-    pub const kWh: &'static UomMeta   = &UomMeta { name: "Kilowatt Hour", multiplier: 1e3 as f64 };
-    pub const Wh: &'static UomMeta    = &UomMeta { name: "Watt Hour", multiplier: 1 as f64 };
-    pub const MW: &'static UomMeta    = &UomMeta { name: "Megawatt", multiplier: 1e6 as f64 };
-    pub const kW: &'static UomMeta    = &UomMeta { name: "Kilowatt", multiplier: 1e3 as f64 };
-    pub const W: &'static UomMeta     = &UomMeta { name: "Watt", multiplier: 1 as f64 };
-    pub const MVArh: &'static UomMeta = &UomMeta { name: "Megavolt Ampere Reactive Hour", multiplier: 1e6 as f64 };
-    pub const kVArh: &'static UomMeta = &UomMeta { name: "Kilovolt Ampere Reactive Hour", multiplier: 1e3 as f64 };
-    pub const VArh: &'static UomMeta  = &UomMeta { name: "Volt Ampere Reactive Hour", multiplier: 1 as f64 };
-    pub const MVAr: &'static UomMeta  = &UomMeta { name: "Megavolt Ampere Reactive", multiplier: 1e6 as f64 };
-    pub const kVAr: &'static UomMeta  = &UomMeta { name: "Kilovolt Ampere Reactive", multiplier: 1e3 as f64 };
-    pub const VAr: &'static UomMeta   = &UomMeta { name: "Volt Ampere Reactive", multiplier: 1 as f64 };
-    pub const MVAh: &'static UomMeta  = &UomMeta { name: "Megavolt Ampere Hour", multiplier: 1e6 as f64 };
-    pub const kVAh: &'static UomMeta  = &UomMeta { name: "Kilovolt Ampere Hour", multiplier: 1e3 as f64 };
-    pub const VAh: &'static UomMeta   = &UomMeta { name: "Volt Ampere Hour", multiplier: 1 as f64 };
-    pub const MVA: &'static UomMeta   = &UomMeta { name: "Megavolt Ampere", multiplier: 1e6 as f64 };
-    pub const kVA: &'static UomMeta   = &UomMeta { name: "Kilovolt Ampere", multiplier: 1e3 as f64 };
-    pub const VA: &'static UomMeta    = &UomMeta { name: "Volt Ampere", multiplier: 1 as f64 };
-    pub const kV: &'static UomMeta    = &UomMeta { name: "Kilovolt", multiplier: 1e3 as f64 };
-    pub const V: &'static UomMeta     = &UomMeta { name: "Volt", multiplier: 1 as f64 };
-    pub const kA: &'static UomMeta    = &UomMeta { name: "Kiloampere", multiplier: 1e3 as f64 };
-    pub const A: &'static UomMeta     = &UomMeta { name: "Ampere", multiplier: 1 as f64 };
-    pub const pf: &'static UomMeta    = &UomMeta { name: "Power Factor", multiplier: 1 as f64 };
+    pub const MWh: &UomMeta   = &UomMeta { name: "Megawatt Hour", multiplier: 1e6 };
+    pub const kWh: &UomMeta   = &UomMeta { name: "Kilowatt Hour", multiplier: 1e3 };
+    pub const Wh: &UomMeta    = &UomMeta { name: "Watt Hour", multiplier: 1.0 };
+    pub const MW: &UomMeta    = &UomMeta { name: "Megawatt", multiplier: 1e6 };
+    pub const kW: &UomMeta    = &UomMeta { name: "Kilowatt", multiplier: 1e3 };
+    pub const W: &UomMeta     = &UomMeta { name: "Watt", multiplier: 1.0 };
+    pub const MVArh: &UomMeta = &UomMeta { name: "Megavolt Ampere Reactive Hour", multiplier: 1e6 };
+    pub const kVArh: &UomMeta = &UomMeta { name: "Kilovolt Ampere Reactive Hour", multiplier: 1e3 };
+    pub const VArh: &UomMeta  = &UomMeta { name: "Volt Ampere Reactive Hour", multiplier: 1.0 };
+    pub const MVAr: &UomMeta  = &UomMeta { name: "Megavolt Ampere Reactive", multiplier: 1e6 };
+    pub const kVAr: &UomMeta  = &UomMeta { name: "Kilovolt Ampere Reactive", multiplier: 1e3 };
+    pub const VAr: &UomMeta   = &UomMeta { name: "Volt Ampere Reactive", multiplier: 1.0 };
+    pub const MVAh: &UomMeta  = &UomMeta { name: "Megavolt Ampere Hour", multiplier: 1e6 };
+    pub const kVAh: &UomMeta  = &UomMeta { name: "Kilovolt Ampere Hour", multiplier: 1e3 };
+    pub const VAh: &UomMeta   = &UomMeta { name: "Volt Ampere Hour", multiplier: 1.0 };
+    pub const MVA: &UomMeta   = &UomMeta { name: "Megavolt Ampere", multiplier: 1e6 };
+    pub const kVA: &UomMeta   = &UomMeta { name: "Kilovolt Ampere", multiplier: 1e3 };
+    pub const VA: &UomMeta    = &UomMeta { name: "Volt Ampere", multiplier: 1.0 };
+    pub const kV: &UomMeta    = &UomMeta { name: "Kilovolt", multiplier: 1e3 };
+    pub const V: &UomMeta     = &UomMeta { name: "Volt", multiplier: 1.0 };
+    pub const kA: &UomMeta    = &UomMeta { name: "Kiloampere", multiplier: 1e3 };
+    pub const A: &UomMeta     = &UomMeta { name: "Ampere", multiplier: 1.0 };
+    pub const pf: &UomMeta    = &UomMeta { name: "Power Factor", multiplier: 1.0 };
 
     pub struct UomMeta {
         name: &'static str,
         multiplier: f64
     }
+
+    impl UomMeta {
+        pub fn name(&self) -> &'static str {
+            self.name
+        }
+
+        /// The factor that converts a value in this unit to the base unit, e.g. `1e3` for kWh to Wh.
+        pub fn multiplier(&self) -> f64 {
+            self.multiplier
+        }
+    }
+}
+
+code_enum! {
+    /// The unit of measure of a 200 or 250 record. See [`UOM`] for names and multipliers.
+    Uom, meta -> &'static UOM::UomMeta {
+        MegawattHour = "MWh" => UOM::MWh,
+        KilowattHour = "kWh" => UOM::kWh,
+        WattHour = "Wh" => UOM::Wh,
+        Megawatt = "MW" => UOM::MW,
+        Kilowatt = "kW" => UOM::kW,
+        Watt = "W" => UOM::W,
+        MegavoltAmpereReactiveHour = "MVArh" => UOM::MVArh,
+        KilovoltAmpereReactiveHour = "kVArh" => UOM::kVArh,
+        VoltAmpereReactiveHour = "VArh" => UOM::VArh,
+        MegavoltAmpereReactive = "MVAr" => UOM::MVAr,
+        KilovoltAmpereReactive = "kVAr" => UOM::kVAr,
+        VoltAmpereReactive = "VAr" => UOM::VAr,
+        MegavoltAmpereHour = "MVAh" => UOM::MVAh,
+        KilovoltAmpereHour = "kVAh" => UOM::kVAh,
+        VoltAmpereHour = "VAh" => UOM::VAh,
+        MegavoltAmpere = "MVA" => UOM::MVA,
+        KilovoltAmpere = "kVA" => UOM::kVA,
+        VoltAmpere = "VA" => UOM::VA,
+        Kilovolt = "kV" => UOM::kV,
+        Volt = "V" => UOM::V,
+        Kiloampere = "kA" => UOM::kA,
+        Ampere = "A" => UOM::A,
+        PowerFactor = "pf" => UOM::pf,
+    }
+}
+
+impl Uom {
+    /// Whether the unit measures energy over each interval, such as `kWh`,
+    /// rather than an instantaneous or average quantity such as `kW` or `V`.
+    pub fn is_energy(&self) -> bool {
+        self.as_str().ends_with('h')
+    }
+}
+
+impl str::FromStr for Uom {
+    type Err = CodeError;
+
+    /// Units are matched case-insensitively, as files use both `kWh` and `KWH`.
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        Uom::ALL.iter().copied().find(|uom| uom.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| CodeError::new("unit of measure", s))
+    }
 }
 
 pub mod QUALITY {
-    pub const A: &'static str = "Actual Data";
-    pub const E: &'static str = "Forward Estimated Data";
-    pub const F: &'static str = "Final Substituted Data";
-    pub const N: &'static str = "Null Data";
-    pub const S: &'static str = "Substituted Data";
-    pub const V: &'static str = "Variable Data";
+    pub const A: &str = "Actual Data";
+    pub const E: &str = "Forward Estimated Data";
+    pub const F: &str = "Final Substituted Data";
+    pub const N: &str = "Null Data";
+    pub const S: &str = "Substituted Data";
+    pub const V: &str = "Variable Data";
+}
+
+/// The quality flag of a 300, 400 or 250 record. See [`QUALITY`] for descriptions.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum Quality {
+    A,
+    E,
+    F,
+    N,
+    S,
+    V,
+}
+
+impl Quality {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'A' => Some(Quality::A),
+            'E' => Some(Quality::E),
+            'F' => Some(Quality::F),
+            'N' => Some(Quality::N),
+            'S' => Some(Quality::S),
+            'V' => Some(Quality::V),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Quality::A => "A",
+            Quality::E => "E",
+            Quality::F => "F",
+            Quality::N => "N",
+            Quality::S => "S",
+            Quality::V => "V",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Quality::A => QUALITY::A,
+            Quality::E => QUALITY::E,
+            Quality::F => QUALITY::F,
+            Quality::N => QUALITY::N,
+            Quality::S => QUALITY::S,
+            Quality::V => QUALITY::V,
+        }
+    }
+
+    /// Estimated and substituted data must say how it was derived, so these
+    /// quality flags are always followed by a method flag.
+    pub fn requires_method(&self) -> bool {
+        matches!(self, Quality::E | Quality::F | Quality::S)
+    }
+
+    /// Ranks qualities from best to worst: actual data, then final and
+    /// provisional substitutions, estimates and finally null data. Variable
+    /// quality stands for a mix of the others and ranks last.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Quality::A => 0,
+            Quality::F => 1,
+            Quality::S => 2,
+            Quality::E => 3,
+            Quality::N => 4,
+            Quality::V => 5,
+        }
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl str::FromStr for Quality {
+    type Err = CodeError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(Quality::from_char), chars.next()) {
+            (Some(quality), None) => Ok(quality),
+            _ => Err(CodeError::new("quality flag", s)),
+        }
+    }
 }
 
 pub mod METHOD {
-    pub const FLAG_11: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Check", description: "" };
-    pub const FLAG_12: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Calculated", description: "" };
-    pub const FLAG_13: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "SCADA", description: "" };
-    pub const FLAG_14: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Like Day", description: "" };
-    pub const FLAG_15: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Average Like Day", description: "" };
-    pub const FLAG_16: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Agreed", description: "" };
-    pub const FLAG_17: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Linear", description: "" };
-    pub const FLAG_18: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Alternate", description: "" };
-    pub const FLAG_19: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Zero", description: "" };
-    pub const FLAG_51: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Previous Year", description: "" };
-    pub const FLAG_52: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Previous Read", description: "" };
-    pub const FLAG_53: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Revision", description: "" };
-    pub const FLAG_54: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Linear", description: "" };
-    pub const FLAG_55: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Agreed", description: "" };
-    pub const FLAG_56: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Prior to First Read - Agreed", description: "" };
-    pub const FLAG_57: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Customer Class", description: "" };
-    pub const FLAG_58: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Zero", description: "" };
-    pub const FLAG_61: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Previous Year", description: "" };
-    pub const FLAG_62: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Previous Read", description: "" };
-    pub const FLAG_63: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Customer Class", description: "" };
-    pub const FLAG_64: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Agreed", description: "" };
-    pub const FLAG_65: &'static MethodMeta = &MethodMeta { typ: &["EST"], installation_type: OneOrArr::One(6), short_descriptor: "ADL", description: "" };
-    pub const FLAG_66: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Revision", description: "" };
-    pub const FLAG_67: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Customer Read", description: "" };
-    pub const FLAG_68: &'static MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Zero", description: "" };
-    pub const FLAG_71: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(7), short_descriptor: "Recalculation", description: "" };
-    pub const FLAG_72: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(7), short_descriptor: "Revised Table", description: "" };
-    pub const FLAG_73: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(7), short_descriptor: "Revised Algorithm", description: "" };
-    pub const FLAG_74: &'static MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(7), short_descriptor: "Agreed", description: "" };
-    pub const FLAG_75: &'static MethodMeta = &MethodMeta { typ: &["EST"], installation_type: OneOrArr::One(7), short_descriptor: "Existing Table", description: "" };
+    pub const FLAG_11: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Check", description: "" };
+    pub const FLAG_12: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Calculated", description: "" };
+    pub const FLAG_13: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "SCADA", description: "" };
+    pub const FLAG_14: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Like Day", description: "" };
+    pub const FLAG_15: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Average Like Day", description: "" };
+    pub const FLAG_16: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Agreed", description: "" };
+    pub const FLAG_17: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Linear", description: "" };
+    pub const FLAG_18: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Alternate", description: "" };
+    pub const FLAG_19: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::Arr(&[&1, &2, &3, &4]), short_descriptor: "Zero", description: "" };
+    pub const FLAG_51: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Previous Year", description: "" };
+    pub const FLAG_52: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Previous Read", description: "" };
+    pub const FLAG_53: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Revision", description: "" };
+    pub const FLAG_54: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Linear", description: "" };
+    pub const FLAG_55: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Agreed", description: "" };
+    pub const FLAG_56: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Prior to First Read - Agreed", description: "" };
+    pub const FLAG_57: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Customer Class", description: "" };
+    pub const FLAG_58: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(5), short_descriptor: "Zero", description: "" };
+    pub const FLAG_61: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Previous Year", description: "" };
+    pub const FLAG_62: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Previous Read", description: "" };
+    pub const FLAG_63: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Customer Class", description: "" };
+    pub const FLAG_64: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Agreed", description: "" };
+    pub const FLAG_65: &MethodMeta = &MethodMeta { typ: &["EST"], installation_type: OneOrArr::One(6), short_descriptor: "ADL", description: "" };
+    pub const FLAG_66: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Revision", description: "" };
+    pub const FLAG_67: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Customer Read", description: "" };
+    pub const FLAG_68: &MethodMeta = &MethodMeta { typ: &["EST", "SUB"], installation_type: OneOrArr::One(6), short_descriptor: "Zero", description: "" };
+    pub const FLAG_71: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(7), short_descriptor: "Recalculation", description: "" };
+    pub const FLAG_72: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(7), short_descriptor: "Revised Table", description: "" };
+    pub const FLAG_73: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(7), short_descriptor: "Revised Algorithm", description: "" };
+    pub const FLAG_74: &MethodMeta = &MethodMeta { typ: &["SUB"], installation_type: OneOrArr::One(7), short_descriptor: "Agreed", description: "" };
+    pub const FLAG_75: &MethodMeta = &MethodMeta { typ: &["EST"], installation_type: OneOrArr::One(7), short_descriptor: "Existing Table", description: "" };
 
     pub struct MethodMeta {
         typ: &'static [&'static str],
@@ -93,149 +354,604 @@ pub mod METHOD {
         description: &'static str,
     }
 
+    impl MethodMeta {
+        /// Whether the method may be used for estimates (`"EST"`), substitutions (`"SUB"`) or both.
+        pub fn typ(&self) -> &'static [&'static str] {
+            self.typ
+        }
+
+        pub fn short_descriptor(&self) -> &'static str {
+            self.short_descriptor
+        }
+
+        pub fn description(&self) -> &'static str {
+            self.description
+        }
+
+        /// Whether the method may be used for the given metering installation type.
+        pub fn applies_to(&self, installation_type: u8) -> bool {
+            match self.installation_type {
+                OneOrArr::One(t) => t == installation_type,
+                OneOrArr::Arr(types) => types.iter().any(|t| **t == installation_type),
+            }
+        }
+    }
+
     enum OneOrArr {
         One(u8),
         Arr(&'static [&'static u8])
     }
 }
 
+macro_rules! method_flags {
+    ($($variant:ident = $code:literal => $meta:ident,)*) => {
+        /// The method flag that follows an E, F or S quality flag. See [`METHOD`] for its metadata.
+        #[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+        pub enum MethodFlag {
+            $($variant,)*
+        }
+
+        impl MethodFlag {
+            pub fn from_code(code: u8) -> Option<Self> {
+                match code {
+                    $($code => Some(MethodFlag::$variant),)*
+                    _ => None,
+                }
+            }
+
+            pub fn code(&self) -> u8 {
+                match self {
+                    $(MethodFlag::$variant => $code,)*
+                }
+            }
+
+            pub fn meta(&self) -> &'static METHOD::MethodMeta {
+                match self {
+                    $(MethodFlag::$variant => METHOD::$meta,)*
+                }
+            }
+        }
+    };
+}
+
+method_flags! {
+    Flag11 = 11 => FLAG_11,
+    Flag12 = 12 => FLAG_12,
+    Flag13 = 13 => FLAG_13,
+    Flag14 = 14 => FLAG_14,
+    Flag15 = 15 => FLAG_15,
+    Flag16 = 16 => FLAG_16,
+    Flag17 = 17 => FLAG_17,
+    Flag18 = 18 => FLAG_18,
+    Flag19 = 19 => FLAG_19,
+    Flag51 = 51 => FLAG_51,
+    Flag52 = 52 => FLAG_52,
+    Flag53 = 53 => FLAG_53,
+    Flag54 = 54 => FLAG_54,
+    Flag55 = 55 => FLAG_55,
+    Flag56 = 56 => FLAG_56,
+    Flag57 = 57 => FLAG_57,
+    Flag58 = 58 => FLAG_58,
+    Flag61 = 61 => FLAG_61,
+    Flag62 = 62 => FLAG_62,
+    Flag63 = 63 => FLAG_63,
+    Flag64 = 64 => FLAG_64,
+    Flag65 = 65 => FLAG_65,
+    Flag66 = 66 => FLAG_66,
+    Flag67 = 67 => FLAG_67,
+    Flag68 = 68 => FLAG_68,
+    Flag71 = 71 => FLAG_71,
+    Flag72 = 72 => FLAG_72,
+    Flag73 = 73 => FLAG_73,
+    Flag74 = 74 => FLAG_74,
+    Flag75 = 75 => FLAG_75,
+}
+
+impl fmt::Display for MethodFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}", self.code())
+    }
+}
+
+impl str::FromStr for MethodFlag {
+    type Err = CodeError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        Some(s)
+            .filter(|s| s.len() == 2 && s.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|s| s.parse().ok())
+            .and_then(MethodFlag::from_code)
+            .ok_or_else(|| CodeError::new("method flag", s))
+    }
+}
+
+/// A quality flag and, for estimated and substituted data, the method flag
+/// that follows it, e.g. `A` or `F14`.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub struct QualityMethod {
+    quality: Quality,
+    method: Option<MethodFlag>,
+}
+
+impl QualityMethod {
+    /// Returns `None` when a method flag is missing for E, F or S data or
+    /// given for any other quality flag. Whether the method suits the quality
+    /// is left to [`QualityMethod::method_fits`].
+    pub fn new(quality: Quality, method: Option<MethodFlag>) -> Option<Self> {
+        if method.is_some() == quality.requires_method() {
+            Some(QualityMethod { quality, method })
+        } else {
+            None
+        }
+    }
+
+    /// Whether the method flag is an estimation method (`EST`) for E data or
+    /// a substitution method (`SUB`) for F and S data. Always true without a
+    /// method flag.
+    pub fn method_fits(&self) -> bool {
+        match (self.quality, self.method) {
+            (Quality::E, Some(method)) => method.meta().typ().contains(&"EST"),
+            (_, Some(method)) => method.meta().typ().contains(&"SUB"),
+            (_, None) => true,
+        }
+    }
+
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+
+    pub fn method(&self) -> Option<MethodFlag> {
+        self.method
+    }
+}
+
+impl fmt::Display for QualityMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.quality)?;
+        if let Some(method) = self.method {
+            write!(f, "{}", method)?;
+        }
+        Ok(())
+    }
+}
+
+impl str::FromStr for QualityMethod {
+    type Err = CodeError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        let quality = s.get(..1).ok_or_else(|| CodeError::new("quality method", s))?;
+        let method = match s.get(1..) {
+            Some("") | None => None,
+            Some(method) => Some(method.parse()?),
+        };
+
+        QualityMethod::new(quality.parse()?, method).ok_or_else(|| CodeError::new("quality method", s))
+    }
+}
+
 pub mod REASON {
-    pub const CODE_0: &'static str = "Free Text Description";
-    pub const CODE_1: &'static str = "Meter/Equipment Changed";
-    pub const CODE_2: &'static str = "Extreme Weather/Wet";
-    pub const CODE_3: &'static str = "Quarantine";
-    pub const CODE_4: &'static str = "Savage Dog";
-    pub const CODE_5: &'static str = "Meter/Equipment Changed";
-    pub const CODE_6: &'static str = "Extreme Weather/Wet";
-    pub const CODE_7: &'static str = "Unable To Locate Meter";
-    pub const CODE_8: &'static str = "Vacant Premise";
-    pub const CODE_9: &'static str = "Meter/Equipment Changed";
-    pub const CODE_10: &'static str = "Lock Damaged/Seized";
-    pub const CODE_11: &'static str = "In Wrong Walk";
-    pub const CODE_12: &'static str = "Locked Premises";
-    pub const CODE_13: &'static str = "Locked Gate";
-    pub const CODE_14: &'static str = "Locked Meter Box";
-    pub const CODE_15: &'static str = "Access - Overgrown";
-    pub const CODE_16: &'static str = "Noxious Weeds";
-    pub const CODE_17: &'static str = "Unsafe Equipment/Location";
-    pub const CODE_18: &'static str = "Read Below Previous";
-    pub const CODE_19: &'static str = "Consumer Wanted";
-    pub const CODE_20: &'static str = "Damaged Equipment/Panel";
-    pub const CODE_21: &'static str = "Switched Off";
-    pub const CODE_22: &'static str = "Meter/Equipment Seals Missing";
-    pub const CODE_23: &'static str = "Meter/Equipment Seals Missing";
-    pub const CODE_24: &'static str = "Meter/Equipment Seals Missing";
-    pub const CODE_25: &'static str = "Meter/Equipment Seals Missing";
-    pub const CODE_26: &'static str = "Meter/Equipment Seals Missing";
-    pub const CODE_27: &'static str = "Meter/Equipment Seals Missing";
-    pub const CODE_28: &'static str = "Damaged Equipment/Panel";
-    pub const CODE_29: &'static str = "Relay Faulty/Damaged";
-    pub const CODE_30: &'static str = "Meter Stop Switch On";
-    pub const CODE_31: &'static str = "Meter/Equipment Seals Missing";
-    pub const CODE_32: &'static str = "Damaged Equipment/Panel";
-    pub const CODE_33: &'static str = "Relay Faulty/Damaged";
-    pub const CODE_34: &'static str = "Meter Not In Handheld";
-    pub const CODE_35: &'static str = "Timeswitch Faulty/Reset Required";
-    pub const CODE_36: &'static str = "Meter High/Ladder Required";
-    pub const CODE_37: &'static str = "Meter High/Ladder Required";
-    pub const CODE_38: &'static str = "Unsafe Equipment/Location";
-    pub const CODE_39: &'static str = "Reverse Energy Observed";
-    pub const CODE_40: &'static str = "Timeswitch Faulty/Reset Required";
-    pub const CODE_41: &'static str = "Faulty Equipment Display/Dials";
-    pub const CODE_42: &'static str = "Faulty Equipment Display/Dials";
-    pub const CODE_43: &'static str = "Power Outage";
-    pub const CODE_44: &'static str = "Unsafe Equipment/Location";
-    pub const CODE_45: &'static str = "Readings Failed To Validate";
-    pub const CODE_46: &'static str = "Extreme Weather/Hot";
-    pub const CODE_47: &'static str = "Refused Access";
-    pub const CODE_48: &'static str = "Timeswitch Faulty/Reset Required";
-    pub const CODE_49: &'static str = "Wet Paint";
-    pub const CODE_50: &'static str = "Wrong Tariff";
-    pub const CODE_51: &'static str = "Installation Demolished";
-    pub const CODE_52: &'static str = "Access - Blocked";
-    pub const CODE_53: &'static str = "Bees/Wasp In Meter Box";
-    pub const CODE_54: &'static str = "Meter Box Damaged/Faulty";
-    pub const CODE_55: &'static str = "Faulty Equipment Display/Dials";
-    pub const CODE_56: &'static str = "Meter Box Damaged/Faulty";
-    pub const CODE_57: &'static str = "Timeswitch Faulty/Reset Required";
-    pub const CODE_58: &'static str = "Meter Ok - Supply Failure";
-    pub const CODE_59: &'static str = "Faulty Equipment Display/Dials";
-    pub const CODE_60: &'static str = "Illegal Connection/Equipment Tampered";
-    pub const CODE_61: &'static str = "Meter Box Damaged/Faulty";
-    pub const CODE_62: &'static str = "Damaged Equipment/Panel";
-    pub const CODE_63: &'static str = "Illegal Connection/Equipment Tampered";
-    pub const CODE_64: &'static str = "Key Required";
-    pub const CODE_65: &'static str = "Wrong Key Provided";
-    pub const CODE_66: &'static str = "Lock Damaged/Seized";
-    pub const CODE_67: &'static str = "Extreme Weather/Wet";
-    pub const CODE_68: &'static str = "Zero Consumption";
-    pub const CODE_69: &'static str = "Reading Exceeds Estimate";
-    pub const CODE_70: &'static str = "Probe Reports Tampering";
-    pub const CODE_71: &'static str = "Probe Read Error";
-    pub const CODE_72: &'static str = "Meter/Equipment Changed";
-    pub const CODE_73: &'static str = "Low Consumption";
-    pub const CODE_74: &'static str = "High Consumption";
-    pub const CODE_75: &'static str = "Customer Read";
-    pub const CODE_76: &'static str = "Communications Fault";
-    pub const CODE_77: &'static str = "Estimation Forecast";
-    pub const CODE_78: &'static str = "Null Data";
-    pub const CODE_79: &'static str = "Power Outage Alarm";
-    pub const CODE_80: &'static str = "Short Interval Alarm";
-    pub const CODE_81: &'static str = "Long Interval Alarm";
-    pub const CODE_82: &'static str = "CRC Error";
-    pub const CODE_83: &'static str = "RAM Checksum Error";
-    pub const CODE_84: &'static str = "ROM Checksum Error";
-    pub const CODE_85: &'static str = "Data Missing Alarm";
-    pub const CODE_86: &'static str = "Clock Error Alarm";
-    pub const CODE_87: &'static str = "Reset Occurred";
-    pub const CODE_88: &'static str = "Watchdog Timeout Alarm";
-    pub const CODE_89: &'static str = "Time Reset Occurred";
-    pub const CODE_90: &'static str = "Test pub mode";
-    pub const CODE_91: &'static str = "Load Control";
-    pub const CODE_92: &'static str = "Added Interval (Data Correction)";
-    pub const CODE_93: &'static str = "Replaced Interval (Data Correction)";
-    pub const CODE_94: &'static str = "Estimated Interval (Data Correction)";
-    pub const CODE_95: &'static str = "Pulse Overflow Alarm";
-    pub const CODE_96: &'static str = "Data Out Of Limits";
-    pub const CODE_97: &'static str = "Excluded Data";
-    pub const CODE_98: &'static str = "Parity Error";
-    pub const CODE_99: &'static str = "Energy Type (Register Changed)";
-}   
+    pub const CODE_0: &str = "Free Text Description";
+    pub const CODE_1: &str = "Meter/Equipment Changed";
+    pub const CODE_2: &str = "Extreme Weather/Wet";
+    pub const CODE_3: &str = "Quarantine";
+    pub const CODE_4: &str = "Savage Dog";
+    pub const CODE_5: &str = "Meter/Equipment Changed";
+    pub const CODE_6: &str = "Extreme Weather/Wet";
+    pub const CODE_7: &str = "Unable To Locate Meter";
+    pub const CODE_8: &str = "Vacant Premise";
+    pub const CODE_9: &str = "Meter/Equipment Changed";
+    pub const CODE_10: &str = "Lock Damaged/Seized";
+    pub const CODE_11: &str = "In Wrong Walk";
+    pub const CODE_12: &str = "Locked Premises";
+    pub const CODE_13: &str = "Locked Gate";
+    pub const CODE_14: &str = "Locked Meter Box";
+    pub const CODE_15: &str = "Access - Overgrown";
+    pub const CODE_16: &str = "Noxious Weeds";
+    pub const CODE_17: &str = "Unsafe Equipment/Location";
+    pub const CODE_18: &str = "Read Below Previous";
+    pub const CODE_19: &str = "Consumer Wanted";
+    pub const CODE_20: &str = "Damaged Equipment/Panel";
+    pub const CODE_21: &str = "Switched Off";
+    pub const CODE_22: &str = "Meter/Equipment Seals Missing";
+    pub const CODE_23: &str = "Meter/Equipment Seals Missing";
+    pub const CODE_24: &str = "Meter/Equipment Seals Missing";
+    pub const CODE_25: &str = "Meter/Equipment Seals Missing";
+    pub const CODE_26: &str = "Meter/Equipment Seals Missing";
+    pub const CODE_27: &str = "Meter/Equipment Seals Missing";
+    pub const CODE_28: &str = "Damaged Equipment/Panel";
+    pub const CODE_29: &str = "Relay Faulty/Damaged";
+    pub const CODE_30: &str = "Meter Stop Switch On";
+    pub const CODE_31: &str = "Meter/Equipment Seals Missing";
+    pub const CODE_32: &str = "Damaged Equipment/Panel";
+    pub const CODE_33: &str = "Relay Faulty/Damaged";
+    pub const CODE_34: &str = "Meter Not In Handheld";
+    pub const CODE_35: &str = "Timeswitch Faulty/Reset Required";
+    pub const CODE_36: &str = "Meter High/Ladder Required";
+    pub const CODE_37: &str = "Meter High/Ladder Required";
+    pub const CODE_38: &str = "Unsafe Equipment/Location";
+    pub const CODE_39: &str = "Reverse Energy Observed";
+    pub const CODE_40: &str = "Timeswitch Faulty/Reset Required";
+    pub const CODE_41: &str = "Faulty Equipment Display/Dials";
+    pub const CODE_42: &str = "Faulty Equipment Display/Dials";
+    pub const CODE_43: &str = "Power Outage";
+    pub const CODE_44: &str = "Unsafe Equipment/Location";
+    pub const CODE_45: &str = "Readings Failed To Validate";
+    pub const CODE_46: &str = "Extreme Weather/Hot";
+    pub const CODE_47: &str = "Refused Access";
+    pub const CODE_48: &str = "Timeswitch Faulty/Reset Required";
+    pub const CODE_49: &str = "Wet Paint";
+    pub const CODE_50: &str = "Wrong Tariff";
+    pub const CODE_51: &str = "Installation Demolished";
+    pub const CODE_52: &str = "Access - Blocked";
+    pub const CODE_53: &str = "Bees/Wasp In Meter Box";
+    pub const CODE_54: &str = "Meter Box Damaged/Faulty";
+    pub const CODE_55: &str = "Faulty Equipment Display/Dials";
+    pub const CODE_56: &str = "Meter Box Damaged/Faulty";
+    pub const CODE_57: &str = "Timeswitch Faulty/Reset Required";
+    pub const CODE_58: &str = "Meter Ok - Supply Failure";
+    pub const CODE_59: &str = "Faulty Equipment Display/Dials";
+    pub const CODE_60: &str = "Illegal Connection/Equipment Tampered";
+    pub const CODE_61: &str = "Meter Box Damaged/Faulty";
+    pub const CODE_62: &str = "Damaged Equipment/Panel";
+    pub const CODE_63: &str = "Illegal Connection/Equipment Tampered";
+    pub const CODE_64: &str = "Key Required";
+    pub const CODE_65: &str = "Wrong Key Provided";
+    pub const CODE_66: &str = "Lock Damaged/Seized";
+    pub const CODE_67: &str = "Extreme Weather/Wet";
+    pub const CODE_68: &str = "Zero Consumption";
+    pub const CODE_69: &str = "Reading Exceeds Estimate";
+    pub const CODE_70: &str = "Probe Reports Tampering";
+    pub const CODE_71: &str = "Probe Read Error";
+    pub const CODE_72: &str = "Meter/Equipment Changed";
+    pub const CODE_73: &str = "Low Consumption";
+    pub const CODE_74: &str = "High Consumption";
+    pub const CODE_75: &str = "Customer Read";
+    pub const CODE_76: &str = "Communications Fault";
+    pub const CODE_77: &str = "Estimation Forecast";
+    pub const CODE_78: &str = "Null Data";
+    pub const CODE_79: &str = "Power Outage Alarm";
+    pub const CODE_80: &str = "Short Interval Alarm";
+    pub const CODE_81: &str = "Long Interval Alarm";
+    pub const CODE_82: &str = "CRC Error";
+    pub const CODE_83: &str = "RAM Checksum Error";
+    pub const CODE_84: &str = "ROM Checksum Error";
+    pub const CODE_85: &str = "Data Missing Alarm";
+    pub const CODE_86: &str = "Clock Error Alarm";
+    pub const CODE_87: &str = "Reset Occurred";
+    pub const CODE_88: &str = "Watchdog Timeout Alarm";
+    pub const CODE_89: &str = "Time Reset Occurred";
+    pub const CODE_90: &str = "Test pub mode";
+    pub const CODE_91: &str = "Load Control";
+    pub const CODE_92: &str = "Added Interval (Data Correction)";
+    pub const CODE_93: &str = "Replaced Interval (Data Correction)";
+    pub const CODE_94: &str = "Estimated Interval (Data Correction)";
+    pub const CODE_95: &str = "Pulse Overflow Alarm";
+    pub const CODE_96: &str = "Data Out Of Limits";
+    pub const CODE_97: &str = "Excluded Data";
+    pub const CODE_98: &str = "Parity Error";
+    pub const CODE_99: &str = "Energy Type (Register Changed)";
+}
+
+const REASON_DESCRIPTIONS: [&str; 100] = [
+    REASON::CODE_0,
+    REASON::CODE_1,
+    REASON::CODE_2,
+    REASON::CODE_3,
+    REASON::CODE_4,
+    REASON::CODE_5,
+    REASON::CODE_6,
+    REASON::CODE_7,
+    REASON::CODE_8,
+    REASON::CODE_9,
+    REASON::CODE_10,
+    REASON::CODE_11,
+    REASON::CODE_12,
+    REASON::CODE_13,
+    REASON::CODE_14,
+    REASON::CODE_15,
+    REASON::CODE_16,
+    REASON::CODE_17,
+    REASON::CODE_18,
+    REASON::CODE_19,
+    REASON::CODE_20,
+    REASON::CODE_21,
+    REASON::CODE_22,
+    REASON::CODE_23,
+    REASON::CODE_24,
+    REASON::CODE_25,
+    REASON::CODE_26,
+    REASON::CODE_27,
+    REASON::CODE_28,
+    REASON::CODE_29,
+    REASON::CODE_30,
+    REASON::CODE_31,
+    REASON::CODE_32,
+    REASON::CODE_33,
+    REASON::CODE_34,
+    REASON::CODE_35,
+    REASON::CODE_36,
+    REASON::CODE_37,
+    REASON::CODE_38,
+    REASON::CODE_39,
+    REASON::CODE_40,
+    REASON::CODE_41,
+    REASON::CODE_42,
+    REASON::CODE_43,
+    REASON::CODE_44,
+    REASON::CODE_45,
+    REASON::CODE_46,
+    REASON::CODE_47,
+    REASON::CODE_48,
+    REASON::CODE_49,
+    REASON::CODE_50,
+    REASON::CODE_51,
+    REASON::CODE_52,
+    REASON::CODE_53,
+    REASON::CODE_54,
+    REASON::CODE_55,
+    REASON::CODE_56,
+    REASON::CODE_57,
+    REASON::CODE_58,
+    REASON::CODE_59,
+    REASON::CODE_60,
+    REASON::CODE_61,
+    REASON::CODE_62,
+    REASON::CODE_63,
+    REASON::CODE_64,
+    REASON::CODE_65,
+    REASON::CODE_66,
+    REASON::CODE_67,
+    REASON::CODE_68,
+    REASON::CODE_69,
+    REASON::CODE_70,
+    REASON::CODE_71,
+    REASON::CODE_72,
+    REASON::CODE_73,
+    REASON::CODE_74,
+    REASON::CODE_75,
+    REASON::CODE_76,
+    REASON::CODE_77,
+    REASON::CODE_78,
+    REASON::CODE_79,
+    REASON::CODE_80,
+    REASON::CODE_81,
+    REASON::CODE_82,
+    REASON::CODE_83,
+    REASON::CODE_84,
+    REASON::CODE_85,
+    REASON::CODE_86,
+    REASON::CODE_87,
+    REASON::CODE_88,
+    REASON::CODE_89,
+    REASON::CODE_90,
+    REASON::CODE_91,
+    REASON::CODE_92,
+    REASON::CODE_93,
+    REASON::CODE_94,
+    REASON::CODE_95,
+    REASON::CODE_96,
+    REASON::CODE_97,
+    REASON::CODE_98,
+    REASON::CODE_99,
+];
+
+/// A reason code, from 0 to 99, explaining why data was estimated, substituted
+/// or could not be read. See [`REASON`] for descriptions.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash,PartialOrd,Ord)]
+pub struct ReasonCode(u8);
+
+impl ReasonCode {
+    pub fn new(code: u8) -> Option<Self> {
+        if (code as usize) < REASON_DESCRIPTIONS.len() {
+            Some(ReasonCode(code))
+        } else {
+            None
+        }
+    }
+
+    pub fn code(&self) -> u8 {
+        self.0
+    }
+
+    pub fn description(&self) -> &'static str {
+        REASON_DESCRIPTIONS[self.0 as usize]
+    }
+
+    /// Code 0 carries its meaning in the record's free text reason description.
+    pub fn is_free_text(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl fmt::Display for ReasonCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl str::FromStr for ReasonCode {
+    type Err = CodeError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        Some(s)
+            .filter(|s| !s.is_empty() && s.len() <= 3 && s.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|s| s.parse().ok())
+            .and_then(ReasonCode::new)
+            .ok_or_else(|| CodeError::new("reason code", s))
+    }
+}
+
 
 pub mod DATA_STREAM_SUFFIX {
     // Averaged Data Streams
-    pub const A: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Average", description: "Import", units: "kWh" };
-    pub const D: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Average", description: "Export", units: "kWh" };
-    pub const J: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Average", description: "Import", units: "kVArh" };
-    pub const P: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Average", description: "Export", units: "kVArh" };
-    pub const S: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Average", description: "",       units: "kVAh" };
+    pub const A: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Average", description: "Import", units: "kWh" };
+    pub const D: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Average", description: "Export", units: "kWh" };
+    pub const J: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Average", description: "Import", units: "kVArh" };
+    pub const P: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Average", description: "Export", units: "kVArh" };
+    pub const S: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Average", description: "",       units: "kVAh" };
     // Master Data Streams
-    pub const B: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "Import", units: "kWh" };
-    pub const E: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "Export", units: "kWh" };
-    pub const K: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "Import", units: "kVArh" };
-    pub const Q: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "Export", units: "kVArh" };
-    pub const T: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "",       units: "kVAh" };
-    pub const G: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "Power Factor", units: "PF" };
-    pub const H: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "Q Metering", units: "Qh" };
-    pub const M: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "Par Metering", units: "parh" };
-    pub const V: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Master",  description: "Volts or V2h or Amps or A2h", units: "" };
+    pub const B: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "Import", units: "kWh" };
+    pub const E: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "Export", units: "kWh" };
+    pub const K: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "Import", units: "kVArh" };
+    pub const Q: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "Export", units: "kVArh" };
+    pub const T: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "",       units: "kVAh" };
+    pub const G: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "Power Factor", units: "PF" };
+    pub const H: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "Q Metering", units: "Qh" };
+    pub const M: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "Par Metering", units: "parh" };
+    pub const V: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Master",  description: "Volts or V2h or Amps or A2h", units: "" };
     // Check Meter Streams
-    pub const C: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Check",  description: "Import", units: "kWh" };
-    pub const F: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Check",  description: "Export", units: "kWh" };
-    pub const L: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Check",  description: "Import", units: "kVArh" };
-    pub const R: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Check",  description: "Export", units: "kVArh" };
-    pub const U: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Check",  description: "",       units: "kVAh" };
-    pub const Y: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Check",  description: "Q Metering",         units: "Qh" };
-    pub const W: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Check",  description: "Par Metering Path",  units: "" };
-    pub const Z: &'static DataStreamSuffix = &DataStreamSuffix { stream: "Check",  description: "Volts or V2h or Amps or A2h",  units: "" };
+    pub const C: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Check",  description: "Import", units: "kWh" };
+    pub const F: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Check",  description: "Export", units: "kWh" };
+    pub const L: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Check",  description: "Import", units: "kVArh" };
+    pub const R: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Check",  description: "Export", units: "kVArh" };
+    pub const U: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Check",  description: "",       units: "kVAh" };
+    pub const Y: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Check",  description: "Q Metering",         units: "Qh" };
+    pub const W: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Check",  description: "Par Metering Path",  units: "" };
+    pub const Z: &DataStreamSuffixMeta = &DataStreamSuffixMeta { stream: "Check",  description: "Volts or V2h or Amps or A2h",  units: "" };
     // Net Meter Streams
     // AEMO: NOTE THAT D AND J ARE PREVIOUSLY DEFINED
     // "D" = { stream: "Net",    description: "Net", units: "kWh" },
     // "J" = { stream: "Net",    description: "Net", units: "kVArh" }
 
-    pub struct DataStreamSuffix<'a> {
-        stream: &'a str,
-        description: &'a str,
-        units: &'a str
+    pub struct DataStreamSuffixMeta {
+        stream: &'static str,
+        description: &'static str,
+        units: &'static str
     }
-}
\ No newline at end of file
+
+    impl DataStreamSuffixMeta {
+        /// Whether the stream is `"Average"`, `"Master"` or `"Check"` data.
+        pub fn stream(&self) -> &'static str {
+            self.stream
+        }
+
+        pub fn description(&self) -> &'static str {
+            self.description
+        }
+
+        pub fn units(&self) -> &'static str {
+            self.units
+        }
+    }
+}
+
+code_enum! {
+    /// The first character of an NMI suffix, identifying the kind of data
+    /// stream. See [`DATA_STREAM_SUFFIX`] for its metadata.
+    DataStreamSuffix, meta -> &'static DATA_STREAM_SUFFIX::DataStreamSuffixMeta {
+        A = "A" => DATA_STREAM_SUFFIX::A,
+        D = "D" => DATA_STREAM_SUFFIX::D,
+        J = "J" => DATA_STREAM_SUFFIX::J,
+        P = "P" => DATA_STREAM_SUFFIX::P,
+        S = "S" => DATA_STREAM_SUFFIX::S,
+        B = "B" => DATA_STREAM_SUFFIX::B,
+        E = "E" => DATA_STREAM_SUFFIX::E,
+        K = "K" => DATA_STREAM_SUFFIX::K,
+        Q = "Q" => DATA_STREAM_SUFFIX::Q,
+        T = "T" => DATA_STREAM_SUFFIX::T,
+        G = "G" => DATA_STREAM_SUFFIX::G,
+        H = "H" => DATA_STREAM_SUFFIX::H,
+        M = "M" => DATA_STREAM_SUFFIX::M,
+        V = "V" => DATA_STREAM_SUFFIX::V,
+        C = "C" => DATA_STREAM_SUFFIX::C,
+        F = "F" => DATA_STREAM_SUFFIX::F,
+        L = "L" => DATA_STREAM_SUFFIX::L,
+        R = "R" => DATA_STREAM_SUFFIX::R,
+        U = "U" => DATA_STREAM_SUFFIX::U,
+        Y = "Y" => DATA_STREAM_SUFFIX::Y,
+        W = "W" => DATA_STREAM_SUFFIX::W,
+        Z = "Z" => DATA_STREAM_SUFFIX::Z,
+    }
+}
+
+impl DataStreamSuffix {
+    /// Reads the data stream from an NMI suffix such as `E1`.
+    pub fn from_nmi_suffix(nmi_suffix: &str) -> Option<Self> {
+        nmi_suffix.get(..1).and_then(|c| c.parse().ok())
+    }
+}
+
+impl str::FromStr for DataStreamSuffix {
+    type Err = CodeError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        DataStreamSuffix::ALL.iter().copied().find(|suffix| suffix.as_str() == s)
+            .ok_or_else(|| CodeError::new("data stream suffix", s))
+    }
+}
+
+try_from_str!(TransactionCode, Uom, Quality, MethodFlag, QualityMethod, ReasonCode, DataStreamSuffix);
+#[cfg(feature = "serde")]
+serde_str!(TransactionCode, Uom, Quality, MethodFlag, QualityMethod, ReasonCode, DataStreamSuffix);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quality_method() {
+        let quality_method = QualityMethod::new(Quality::F, Some(MethodFlag::Flag14)).unwrap();
+        assert_eq!(quality_method.to_string(),"F14");
+        assert_eq!(quality_method.quality().description(),"Final Substituted Data");
+        assert_eq!(quality_method.method().unwrap().meta().short_descriptor(),"Like Day");
+
+        assert_eq!(QualityMethod::new(Quality::A, None).unwrap().to_string(),"A");
+        assert_eq!(QualityMethod::new(Quality::A, Some(MethodFlag::Flag14)),None);
+        assert_eq!(QualityMethod::new(Quality::F, None),None);
+        assert!(!QualityMethod::new(Quality::E, Some(MethodFlag::Flag53)).unwrap().method_fits());
+        assert!(!QualityMethod::new(Quality::S, Some(MethodFlag::Flag65)).unwrap().method_fits());
+        assert!(QualityMethod::new(Quality::E, Some(MethodFlag::Flag52)).unwrap().method_fits());
+        assert!(QualityMethod::new(Quality::S, Some(MethodFlag::Flag52)).unwrap().method_fits());
+        assert!(QualityMethod::new(Quality::A, None).unwrap().method_fits());
+        assert_eq!(MethodFlag::from_code(20),None);
+        assert_eq!(MethodFlag::from_code(65).map(|m| m.meta().typ()),Some(&["EST"][..]));
+    }
+
+    #[test]
+    fn parse_codes() {
+        assert_eq!("S".parse::<TransactionCode>().map(|c| c.description()),Ok("Special Read"));
+        assert_eq!(TransactionCode::try_from("X"),Err(CodeError::new("transaction code","X")));
+
+        assert_eq!("kWh".parse::<Uom>(),Ok(Uom::KilowattHour));
+        assert_eq!("KWH".parse::<Uom>(),Ok(Uom::KilowattHour));
+        assert_eq!(Uom::try_from("VArh").map(|u| u.meta().multiplier()),Ok(1.0));
+        assert_eq!(Uom::MegawattHour.to_string(),"MWh");
+        assert!("kWhr".parse::<Uom>().is_err());
+        assert!(Uom::KilovoltAmpereReactiveHour.is_energy());
+        assert!(!Uom::Kilowatt.is_energy() && !Uom::Volt.is_energy() && !Uom::PowerFactor.is_energy());
+
+        assert_eq!("F".parse::<Quality>(),Ok(Quality::F));
+        assert!("FF".parse::<Quality>().is_err());
+        assert!(Quality::A.rank() < Quality::F.rank() && Quality::S.rank() < Quality::E.rank() && Quality::E.rank() < Quality::N.rank());
+
+        assert_eq!("14".parse::<MethodFlag>(),Ok(MethodFlag::Flag14));
+        assert!("4".parse::<MethodFlag>().is_err());
+        assert!("+14".parse::<MethodFlag>().is_err());
+        assert!(MethodFlag::Flag14.meta().applies_to(2));
+        assert!(!MethodFlag::Flag14.meta().applies_to(6));
+
+        assert_eq!("E62".parse::<QualityMethod>().map(|q| q.to_string()),Ok("E62".to_owned()));
+        assert!("A14".parse::<QualityMethod>().is_err());
+        assert!("".parse::<QualityMethod>().is_err());
+
+        assert_eq!("76".parse::<ReasonCode>().map(|r| r.description()),Ok("Communications Fault"));
+        assert_eq!(ReasonCode::try_from("0").map(|r| r.is_free_text()),Ok(true));
+        assert!("100".parse::<ReasonCode>().is_err());
+        assert_eq!(ReasonCode::new(99).map(|r| r.to_string()),Some("99".to_owned()));
+
+        assert_eq!(DataStreamSuffix::from_nmi_suffix("E1"),Some(DataStreamSuffix::E));
+        assert_eq!(DataStreamSuffix::E.meta().description(),"Export");
+        assert_eq!(DataStreamSuffix::from_nmi_suffix("11"),None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_codes() {
+        assert_eq!(serde_json::to_string(&Uom::KilowattHour).unwrap(),"\"kWh\"");
+        assert_eq!(serde_json::from_str::<Uom>("\"KWH\"").unwrap(),Uom::KilowattHour);
+        assert_eq!(serde_json::to_string(&MethodFlag::Flag14).unwrap(),"\"14\"");
+        assert_eq!(serde_json::to_string(&ReasonCode::new(76).unwrap()).unwrap(),"\"76\"");
+        assert_eq!(serde_json::from_str::<QualityMethod>("\"S53\"").map(|q| q.to_string()).unwrap(),"S53");
+        assert!(serde_json::from_str::<QualityMethod>("\"A14\"").is_err());
+        assert!(serde_json::from_str::<Quality>("1").is_err());
+    }
+}
@@ -1,41 +1,272 @@
 use nom::{
-    bytes::complete::{take,tag},
-    character::complete::newline,
+    bytes::complete::{take,tag,take_till,take_while1},
+    character::complete::{alpha1,char,digit0,digit1,one_of},
     // end of streaming combinator imports
-    multi::many1_count,
-    IResult,
-    combinator::{verify,peek,},
-    error,
+    combinator::{map_opt,opt,verify,peek,recognize},
+    sequence::{pair,preceded,tuple},
+    error::{self,ErrorKind},
     InputTake, Compare, InputLength
 };
 use chrono::{NaiveDateTime,NaiveDate};
 
+use std::borrow::Cow;
 use std::fmt;
 use std::str;
 use nom_locate::LocatedSpan;
 
+use crate::codes::{CodeError, MethodFlag, Quality, QualityMethod, TransactionCode, Uom};
+use crate::error::{Warning, WarningKind};
+
 pub type Input<'a> = LocatedSpan<&'a str>;
 
-pub fn rec_separator(input: Input) -> IResult<Input,usize> {
-    many1_count(newline)(input)
+/// The MDFF file format, as named in the 100 header and in file names.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
+pub enum Format {
+    Nem12,
+    Nem13,
+}
+
+impl Format {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Nem12 => "NEM12",
+            Format::Nem13 => "NEM13",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl str::FromStr for Format {
+    type Err = CodeError;
+
+    /// Matches the format name case-insensitively, as file names use both `NEM12` and `nem12`.
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        if s.eq_ignore_ascii_case("NEM12") {
+            Ok(Format::Nem12)
+        } else if s.eq_ignore_ascii_case("NEM13") {
+            Ok(Format::Nem13)
+        } else {
+            Err(CodeError::new("format", s))
+        }
+    }
+}
+
+pub type IResult<I,O,E = FieldError<I>> = nom::IResult<I,O,E>;
+
+/// The error produced by the record parsers. Along with the position and kind
+/// of the failure it records which field was being parsed and what it should
+/// have contained, when known.
+#[derive(Clone,Debug,PartialEq)]
+pub struct FieldError<I> {
+    pub input: I,
+    pub code: ErrorKind,
+    pub field: Option<&'static str>,
+    pub expected: Option<&'static str>,
+}
+
+impl <I>error::ParseError<I> for FieldError<I> {
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        FieldError { input, code: kind, field: None, expected: None }
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl <I,E>error::FromExternalError<I,E> for FieldError<I> {
+    fn from_external_error(input: I, kind: ErrorKind, _e: E) -> Self {
+        FieldError { input, code: kind, field: None, expected: None }
+    }
+}
+
+/// Names the field parsed by `parser`, so that a failure reports the field,
+/// its expected content and the position at which the field starts.
+///
+/// A field must take up the whole of its comma separated value. Fields are
+/// only parsed once a record indicator has matched, so a field failure is
+/// returned as `Err::Failure` to stop enclosing parsers from backtracking and
+/// reporting a less specific error.
+pub fn field<'a, O, F>(
+    name: &'static str,
+    expected: &'static str,
+    mut parser: F
+) -> impl FnMut(Input<'a>) -> IResult<Input<'a>, O>
+where
+    F: FnMut(Input<'a>) -> IResult<Input<'a>, O>
+{
+    move |input: Input<'a>| {
+        let fail = |code| FieldError { input, code, field: Some(name), expected: Some(expected) };
+        match parser(input) {
+            Ok((rest,_)) if !rest.is_empty() && !rest.starts_with([',', '\r', '\n']) => {
+                Err(nom::Err::Failure(fail(ErrorKind::Verify)))
+            }
+            Ok(res) => Ok(res),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) if e.field.is_some() => Err(nom::Err::Failure(e)),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(nom::Err::Failure(fail(e.code))),
+            Err(incomplete) => Err(incomplete),
+        }
+    }
+}
+
+/// Takes the text of a parsed field, borrowed from the source.
+pub fn text(input: Input<'_>) -> Cow<'_, str> {
+    Cow::Borrowed(input.into_fragment())
+}
+
+/// Copies text out of the source. See the `into_owned` methods of the records.
+pub fn owned(text: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(text.into_owned())
+}
+
+/// Whether `input` is at the end of a record.
+pub fn at_end_of_record(input: Input) -> bool {
+    input.is_empty() || input.starts_with(['\r', '\n'])
 }
 
-pub fn section_of_max_length<'a, E: error::ParseError<Input<'a>>, F: Copy>(
+/// Parses a comma and the trailing field after it. When `allow_missing` is
+/// set, a record that ends before the comma gives `None` instead of an error.
+pub fn trailing_field<'a, O, F>(
+    allow_missing: bool,
+    mut parser: F
+) -> impl FnMut(Input<'a>) -> IResult<Input<'a>, Option<O>>
+where
+    F: FnMut(Input<'a>) -> IResult<Input<'a>, Option<O>>
+{
+    move |input: Input<'a>| {
+        if allow_missing && at_end_of_record(input) {
+            return Ok((input,None));
+        }
+        let (input,_) = tag(",")(input)?;
+        parser(input)
+    }
+}
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Removes the UTF-8 byte order mark that some Windows tools write before the 100 header record.
+pub fn strip_bom(input: &str) -> &str {
+    input.strip_prefix(BYTE_ORDER_MARK).unwrap_or(input)
+}
+
+/// Tracks the layout of a file line by line, noting what the parsers accept
+/// but the MDFF specification doesn't: a byte order mark, blank lines, and
+/// line endings that change part way through the file.
+#[derive(Debug,Default)]
+pub(crate) struct Layout {
+    line_ending: Option<&'static str>,
+    pub warnings: Vec<Warning>,
+}
+
+impl Layout {
+    /// Checks one line, including its line ending if it has one.
+    pub fn line(&mut self, line_no: usize, line: &str) {
+        let (text,ending) = match line.strip_suffix('\n') {
+            Some(text) => match text.strip_suffix('\r') {
+                Some(text) => (text,Some("\r\n")),
+                None => (text,Some("\n")),
+            },
+            None => (line,None),
+        };
+        let warning = |kind| Warning { line: line_no, kind };
+
+        if line_no == 1 && text.starts_with(BYTE_ORDER_MARK) {
+            self.warnings.push(warning(WarningKind::ByteOrderMark));
+        }
+        if text.is_empty() {
+            self.warnings.push(warning(WarningKind::BlankLine));
+        }
+        match (self.line_ending,ending) {
+            (None,Some(ending)) => self.line_ending = Some(ending),
+            (Some(first),Some(ending)) if first != ending => self.warnings.push(warning(WarningKind::MixedLineEndings)),
+            _ => {}
+        }
+    }
+}
+
+/// The layout warnings for a whole file. See [`Layout`].
+pub(crate) fn layout_warnings(input: &str) -> Vec<Warning> {
+    let mut layout = Layout::default();
+    for (i,line) in input.split_inclusive('\n').enumerate() {
+        layout.line(i + 1, line);
+    }
+    layout.warnings
+}
+
+/// Splits the next line off `input`, without its `\n` or `\r\n` line ending
+/// and skipping blank lines. Returns `None` at the end of the input.
+pub(crate) fn next_line<'a>(input: &mut Input<'a>) -> Option<Input<'a>> {
+    loop {
+        if input.is_empty() {
+            return None;
+        }
+
+        let (rest,line) = take_till::<_,Input,FieldError<Input>>(|c| c == '\n')(*input).ok()?;
+        let (rest,_) = opt(char::<Input,FieldError<Input>>('\n'))(rest).ok()?;
+        *input = rest;
+
+        let line = match line.strip_suffix('\r') {
+            Some(text) => InputTake::take(&line, text.len()),
+            None => line,
+        };
+
+        if !line.is_empty() {
+            return Some(line);
+        }
+    }
+}
+
+/// Drops the commas after the last of a record's `field_count` fields, when
+/// nothing but commas follows it. Returns `None` when there are none to drop.
+pub(crate) fn strip_extra_commas(line: Input, field_count: usize) -> Option<Input> {
+    let end = line.match_indices(',').nth(field_count - 1).map(|(i,_)| i)?;
+    line[end..].bytes().all(|b| b == b',').then(|| InputTake::take(&line, end))
+}
+
+/// Whether `uom` names a known unit, but is written neither as in the
+/// specification, e.g. `kVArh`, nor all in capitals, e.g. `KVARH`.
+pub(crate) fn uom_in_nonstandard_case(uom: &str) -> bool {
+    match uom.parse::<Uom>() {
+        Ok(known) => uom != known.as_str() && uom != known.as_str().to_ascii_uppercase(),
+        Err(_) => false,
+    }
+}
+
+/// Whether `c` may appear in a free-text or identifier field, which the
+/// specification gives as `Varchar`: printable ASCII other than the comma
+/// that separates fields.
+fn is_varchar(c: char) -> bool {
+    c == ' ' || (c.is_ascii_graphic() && c != ',')
+}
+
+/// Parses the text of a `Varchar` field, up to the comma or line ending after it.
+pub fn varchar(input: Input) -> IResult<Input,Input> {
+    take_while1(is_varchar)(input)
+}
+
+pub fn section_of_max_length<'a, E: error::ParseError<Input<'a>>, F>(
     test: F,
     length: usize
 ) -> impl FnMut(Input<'a>) -> IResult<Input<'a>, Input<'a>, E>
 where
-    F: Fn(Input<'a>) -> IResult<Input<'a>, Input<'a>, E>
+    F: Fn(Input<'a>) -> IResult<Input<'a>, Input<'a>, E> + Copy
 {
     verify(test, move |s: &Input| (s.len() <= length) && (s.len() > 0))
 }
 
-pub fn section_of_exact_length<'a, E: error::ParseError<Input<'a>>, F: Copy>(
+pub fn section_of_exact_length<'a, E: error::ParseError<Input<'a>>, F>(
     test: F,
     length: usize
 ) -> impl FnMut(Input<'a>) -> IResult<Input<'a>, Input<'a>, E>
 where
-    F: Fn(Input<'a>) -> IResult<Input<'a>, Input<'a>, E>
+    F: Fn(Input<'a>) -> IResult<Input<'a>, Input<'a>, E> + Copy
 {
     verify(test, move |s: &Input| s.len() == length)
 }
@@ -54,21 +285,52 @@ where
     move |input: I1| {
         let i = input.clone();
         match test(input) {
-            Ok(d) => Ok((d.0,Some(d.1))),
-            Err(nom::Err::Error(_)) => {
-                match peek(tag::<T,I1,E1>(end_marker))(i) {
-                    Ok((input,_)) => Ok((input,None)),
-                    Err(nom::Err::Error(e)) => {
-                        return Err(nom::Err::Error(e))
-                    }
-                    x => { println!("'{:?}'", x); panic!("This should never happen") }
-                }
-            },
-            x => { println!("{:?}", x); panic!("This should never happen") }
+            Ok((input,o)) => Ok((input,Some(o))),
+            Err(nom::Err::Error(_)) => peek(tag::<T,I1,E1>(end_marker))(i).map(|(input,_)| (input,None)),
+            Err(e) => Err(e),
         }
     }
 }
 
+/// The most characters a numeric value may take up, including its sign and decimal point.
+pub const MAX_NUMERIC_LENGTH: usize = 15;
+
+/// The most decimal places an interval value may have.
+pub const MAX_DECIMAL_PLACES: usize = 5;
+
+/// Parses the text of a decimal number as MDFF files write them, e.g. `18.023`,
+/// `.25` or `-1.5`. Exponents, a leading `+`, `inf` and `NaN` are not numbers here.
+pub fn decimal(input: Input) -> IResult<Input,Input> {
+    verify(
+        recognize(tuple((opt(char('-')), digit0, opt(preceded(char('.'), digit1))))),
+        |s: &Input| s.contains(|c: char| c.is_ascii_digit())
+    )(input)
+}
+
+/// The number of digits after the decimal point of a number parsed by [`decimal`].
+pub fn decimal_places(number: &str) -> usize {
+    number.split_once('.').map_or(0, |(_,fraction)| fraction.len())
+}
+
+/// Parses the transaction code of a 500 or 550 record, e.g. `S`.
+pub fn transaction_code(input: Input) -> IResult<Input,Input> {
+    verify(section_of_exact_length(alpha1, 1), |code: &Input| code.parse::<TransactionCode>().is_ok())(input)
+}
+
+/// Parses a quality flag and its method flag, if any, e.g. `A` or `F14`.
+pub fn quality_method(input: Input) -> IResult<Input,QualityMethod> {
+    map_opt(
+        pair(one_of("AEFNSV"), opt(section_of_exact_length(digit1, 2))),
+        |(quality, method): (char, Option<Input>)| {
+            let method = match method {
+                Some(method) => Some(MethodFlag::from_code(method.parse().ok()?)?),
+                None => None,
+            };
+            QualityMethod::new(Quality::from_char(quality)?, method)
+        }
+    )(input)
+}
+
 pub fn datetime_14(input: Input) -> IResult<Input,NaiveDateTime> {
     let (input, date_time_str) = take(14usize)(input)?;
 
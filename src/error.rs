@@ -0,0 +1,320 @@
+use nom::error::ErrorKind;
+
+use std::{fmt, io};
+
+use crate::common::{FieldError, Input};
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    /// A record that does not match its grammar.
+    Record {
+        /// The record indicator of the failing line, e.g. `"300"`, when it is a known record type.
+        record: Option<&'static str>,
+        /// The name of the field that failed to parse, e.g. `"nmi_suffix"`.
+        field: Option<&'static str>,
+        line: usize,
+        column: usize,
+        expected: Option<&'static str>,
+        found: String,
+        kind: ErrorKind,
+    },
+    /// A record out of the order 100, then blocks of 200, 300, 400 and 500, then 900.
+    Structure { line: usize, error: StructureError },
+}
+
+/// A record in the wrong place in the file, or a file cut short.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum StructureError {
+    /// The file does not start with a 100 header record.
+    MissingHeader,
+    /// A 100 header record after the first line.
+    UnexpectedHeader,
+    /// A 300 record before any 200 record.
+    IntervalDataBeforeDetails,
+    /// A 300 record after a 500 record that has no 300 record before it in
+    /// its block.
+    IntervalDataAfterB2BDetails,
+    /// A 400 record that does not follow a 300 or 400 record.
+    IntervalEventWithoutIntervalData,
+    /// A 500 record before any 200 record.
+    B2BDetailsBeforeDetails,
+    /// A 900 record before any 200 record.
+    MissingDetails,
+    /// A record after the 900 end of data record.
+    RecordAfterEndOfData,
+    /// The file ends without a 900 end of data record.
+    MissingEndOfData,
+    /// A block read by [`crate::Nem12Reader`] that does not start with a 200 record.
+    ExpectedDetails,
+    /// A line in a block read by [`crate::Nem12Reader`] that is not part of the block.
+    UnexpectedRecord,
+    /// A record that ends early.
+    IncompleteRecord,
+}
+
+impl StructureError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            StructureError::MissingHeader => "expected 100 header record",
+            StructureError::UnexpectedHeader => "unexpected 100 record",
+            StructureError::IntervalDataBeforeDetails => "300 record before any 200 record",
+            StructureError::IntervalDataAfterB2BDetails => "300 record after a 500 record with no 300 record before it",
+            StructureError::IntervalEventWithoutIntervalData => "400 record without a preceding 300 record",
+            StructureError::B2BDetailsBeforeDetails => "500 record before any 200 record",
+            StructureError::MissingDetails => "900 record before any 200 record",
+            StructureError::RecordAfterEndOfData => "record after 900 end of data record",
+            StructureError::MissingEndOfData => "missing 900 end of data record",
+            StructureError::ExpectedDetails => "expected 200 record",
+            StructureError::UnexpectedRecord => "unexpected record in NMI data details block",
+            StructureError::IncompleteRecord => "incomplete record",
+        }
+    }
+}
+
+impl fmt::Display for StructureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// Something a parser accepted that doesn't follow the MDFF specification to
+/// the letter. Warnings are only collected by the `parse_with` functions, such
+/// as [`crate::nem12::parse_with`], and the strict parsers, such as
+/// [`crate::nem12::parse_strict`].
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub struct Warning {
+    pub line: usize,
+    pub kind: WarningKind,
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum WarningKind {
+    /// A UTF-8 byte order mark before the 100 header record.
+    ByteOrderMark,
+    /// An empty line between or after records.
+    BlankLine,
+    /// A line ending with `\n` in a file whose first line ends with `\r\n`, or the other way around.
+    MixedLineEndings,
+    /// A unit of measure written in a case the specification doesn't use, such as `kwh`.
+    UomCase,
+    /// Optional fields missing from the end of a record. Only accepted by a
+    /// [`crate::ValidationProfile::Lenient`] parse, as are the kinds below.
+    MissingTrailingFields,
+    /// Commas after the last field of a record.
+    ExtraTrailingCommas,
+    /// A space before the update date and time of a 250 record.
+    SpaceBeforeUpdateDateTime,
+}
+
+impl WarningKind {
+    pub fn message(&self) -> &'static str {
+        match self {
+            WarningKind::ByteOrderMark => "byte order mark before 100 header record",
+            WarningKind::BlankLine => "blank line",
+            WarningKind::MixedLineEndings => "line ending differs from the first line",
+            WarningKind::UomCase => "unit of measure in nonstandard case",
+            WarningKind::MissingTrailingFields => "optional fields missing from the end of the record",
+            WarningKind::ExtraTrailingCommas => "extra commas at the end of the record",
+            WarningKind::SpaceBeforeUpdateDateTime => "space before the update date and time",
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind.message())
+    }
+}
+
+const RECORD_TYPES: [&str; 8] = ["100", "200", "250", "300", "400", "500", "550", "900"];
+
+impl ParseError {
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseError::Io(_) => None,
+            ParseError::Record { line, .. } | ParseError::Structure { line, .. } => Some(*line),
+        }
+    }
+
+    /// Moves the error `offset` lines down, for lines parsed apart from those before them.
+    pub(crate) fn offset_line(mut self, offset: usize) -> Self {
+        if let ParseError::Record { line, .. } | ParseError::Structure { line, .. } = &mut self {
+            *line += offset;
+        }
+        self
+    }
+
+    pub fn record(&self) -> Option<&'static str> {
+        match self {
+            ParseError::Record { record, .. } => *record,
+            _ => None,
+        }
+    }
+
+    pub fn structure(&self) -> Option<StructureError> {
+        match self {
+            ParseError::Structure { error, .. } => Some(*error),
+            _ => None,
+        }
+    }
+
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            ParseError::Record { field, .. } => *field,
+            _ => None,
+        }
+    }
+
+    /// Converts a nom error into a `ParseError`. `source` is the input that was
+    /// given to the failing parser, and `first_line` the line number in the
+    /// file of the first line of `source`.
+    pub(crate) fn from_nom(err: nom::Err<FieldError<Input>>, source: Input, first_line: usize) -> Self {
+        let e = match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e,
+            nom::Err::Incomplete(_) => return ParseError::Structure { line: first_line, error: StructureError::IncompleteRecord },
+        };
+
+        let offset = e.input.location_offset().saturating_sub(source.location_offset());
+        let line_start = source.fragment().get(..offset)
+            .and_then(|before| before.rfind('\n'))
+            .map_or(0, |i| i + 1);
+        let record = source.fragment().get(line_start..)
+            .and_then(|line| line.get(..3))
+            .and_then(|indicator| RECORD_TYPES.iter().find(|r| **r == indicator).copied());
+
+        let column = e.input.get_utf8_column();
+        let expected = match (e.field, e.code) {
+            (Some(_), _) => e.expected,
+            (None, ErrorKind::Tag) if column == 1 => Some("record indicator"),
+            (None, ErrorKind::Tag) => Some("','"),
+            (None, ErrorKind::Eof) => Some("end of record"),
+            (None, ErrorKind::Many1Count) => Some("end of line"),
+            (None, _) => None,
+        };
+        let found = e.input.fragment()
+            .split([',', '\r', '\n'])
+            .next()
+            .unwrap_or("")
+            .to_owned();
+
+        ParseError::Record {
+            record,
+            field: e.field,
+            line: first_line + e.input.location_line() as usize - source.location_line() as usize,
+            column,
+            expected,
+            found,
+            kind: e.code,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "failed to read input: {}", e),
+            ParseError::Record { record, field, line, column, expected, found, kind } => {
+                write!(f, "line {}, column {}", line, column)?;
+                if let Some(record) = record {
+                    write!(f, ", {} record", record)?;
+                }
+                if let Some(field) = field {
+                    write!(f, ", field {}", field)?;
+                }
+                match expected {
+                    Some(expected) => write!(f, ": expected {}, found '{}'", expected, found),
+                    None => write!(f, ": {}, found '{}'", kind.description(), found),
+                }
+            }
+            ParseError::Structure { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEM12_WITH_QUALITY: &str = "100,NEM12,200404201300,MDA1,Ret1\n\
+    200,CCCC123456,E1,001,E1,N1,METSER123,kWh,30,\n\
+    300,20040417,18.023,19.150,17.592,24.155,18.568,22.304,19.222,19.032,19.090,22.237,24.350,22.274,20.193,16.615,19.575,20.391,16.459,20.527,21.438,19.327,21.424,16.656,17.616,18.416,16.666,19.961,18.120,18.023,18.588,21.759,17.841,19.548,18.486,21.391,15.656,16.634,16.377,14.246,17.451,15.742,18.038,18.470,14.936,17.987,15.751,19.750,16.202,14.733,V,,,20040418203500,20040419003500\n\
+    400,1,20,F14,76,\n\
+    400,21,24,A,,\n\
+    400,25,48,S14,1,\n\
+    900\n";
+
+    #[test]
+    fn field_errors() {
+        let input = NEM12_WITH_QUALITY.replace("CCCC123456,E1,001,E1","CCCC123456,E1,001,E12");
+        let err = crate::nem12::parse(&input).unwrap_err();
+        assert!(matches!(&err,ParseError::Record { record: Some("200"), field: Some("nmi_suffix"), line: 2, column: 23, expected: Some("2 alphanumeric characters"), found, .. } if found == "E12"));
+        assert_eq!(err.to_string(),"line 2, column 23, 200 record, field nmi_suffix: expected 2 alphanumeric characters, found 'E12'");
+
+        let input = NEM12_WITH_QUALITY.replace("19.222,","19.2x2,");
+        let err = crate::nem12::parse(&input).unwrap_err();
+        assert_eq!(err.to_string(),"line 3, column 56, 300 record, field interval_value: expected decimal number, found '19.2x2'");
+
+        let input = NEM12_WITH_QUALITY.replace("19.222,","x,");
+        let err = crate::nem12::parse(&input).unwrap_err();
+        assert_eq!(err.to_string(),"line 3, column 56, 300 record, field interval_value: expected decimal number, found 'x'");
+
+        let input = NEM12_WITH_QUALITY.replace("400,21,24,A,,","400,21,24,A,,,");
+        let err = crate::nem12::parse(&input).unwrap_err();
+        assert_eq!((err.record(),err.line()),(Some("400"),Some(5)));
+    }
+
+    #[test]
+    fn structure_errors() {
+        let input = NEM12_WITH_QUALITY.replacen("NEM12","NEM13",1);
+        let err = crate::nem12::parse(&input).unwrap_err();
+        assert_eq!(err.to_string(),"line 1, column 5, 100 record, field format: expected NEM12, found 'NEM13'");
+
+        let input = NEM12_WITH_QUALITY.replace("900\n","");
+        let err = crate::nem12::parse(&input).unwrap_err();
+        assert_eq!(err.to_string(),"line 7: missing 900 end of data record");
+
+        let input = NEM12_WITH_QUALITY.replace("400,21,24,A,,","600,21,24,A,,");
+        let err = crate::nem12::parse(&input).unwrap_err();
+        assert_eq!(err.to_string(),"line 5, column 1: expected record indicator, found '600'");
+    }
+
+    #[test]
+    fn record_order() {
+        fn structure(input: &str) -> (Option<usize>,Option<StructureError>) {
+            let err = crate::nem12::parse(input).unwrap_err();
+            (err.line(),err.structure())
+        }
+        let header = "100,NEM12,200404201300,MDA1,Ret1\n";
+        let b2b_details = "500,S,RETNSRVCEORD1,20031220154500,001123.5\n";
+        let interval_data = NEM12_WITH_QUALITY.lines().nth(2).unwrap();
+
+        assert_eq!(structure(""),(Some(1),Some(StructureError::MissingHeader)));
+        assert_eq!(structure(&NEM12_WITH_QUALITY.replacen(header,"",1)),(Some(1),Some(StructureError::MissingHeader)));
+        assert_eq!(structure(&NEM12_WITH_QUALITY.replacen(header,&header.repeat(2),1)),(Some(2),Some(StructureError::UnexpectedHeader)));
+        assert_eq!(structure(&format!("{}900\n",header)),(Some(2),Some(StructureError::MissingDetails)));
+        assert_eq!(structure(&format!("{}{}",header,b2b_details)),(Some(2),Some(StructureError::B2BDetailsBeforeDetails)));
+        assert_eq!(structure(&NEM12_WITH_QUALITY.replacen("200,CCCC123456,E1,001,E1,N1,METSER123,kWh,30,\n","",1)),(Some(2),Some(StructureError::IntervalDataBeforeDetails)));
+        assert_eq!(structure(&NEM12_WITH_QUALITY.replacen(interval_data,&format!("{}{}",b2b_details,interval_data),1)),(Some(4),Some(StructureError::IntervalDataAfterB2BDetails)));
+        assert_eq!(structure(&NEM12_WITH_QUALITY.replace("400,21,24,A,,\n",&format!("{}400,21,24,A,,\n",b2b_details))),(Some(6),Some(StructureError::IntervalEventWithoutIntervalData)));
+        assert_eq!(structure(&format!("{}{}",NEM12_WITH_QUALITY,b2b_details)),(Some(8),Some(StructureError::RecordAfterEndOfData)));
+
+        let err = crate::nem12::parse(&NEM12_WITH_QUALITY.replacen(interval_data,&format!("{}{}",b2b_details,interval_data),1)).unwrap_err();
+        assert_eq!(err.to_string(),"line 4: 300 record after a 500 record with no 300 record before it");
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fmt, str};
+
+use crate::{common::Format, nem12::record::Header};
+
+/// An MDFF file name of the form `NEM12#UniqueID#From#To`, with an optional
+/// extension such as `.csv` or `.zip`.
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+pub struct FileName {
+    format: Format,
+    unique_id: String,
+    from_participant: String,
+    to_participant: String,
+    extension: Option<String>,
+}
+
+impl FileName {
+    pub fn new(format: Format, unique_id: &str, from_participant: &str, to_participant: &str) -> Result<Self,FileNameError> {
+        Ok(FileName {
+            format,
+            unique_id: field("unique_id", unique_id, 36, |c| c.is_ascii_alphanumeric() || c == '_' || c == '-')?.to_owned(),
+            from_participant: field("from_participant", from_participant, 10, |c| c.is_ascii_alphanumeric())?.to_owned(),
+            to_participant: field("to_participant", to_participant, 10, |c| c.is_ascii_alphanumeric())?.to_owned(),
+            extension: None,
+        })
+    }
+
+    pub fn parse(name: &str) -> Result<Self,FileNameError> {
+        let mut parts = name.split('#');
+
+        let format = parts.next().ok_or(FileNameError::MissingField("format"))?;
+        let format = format.parse::<Format>()
+            .map_err(|_| FileNameError::InvalidField { field: "format", value: format.to_owned() })?;
+        let unique_id = parts.next().ok_or(FileNameError::MissingField("unique_id"))?;
+        let from_participant = parts.next().ok_or(FileNameError::MissingField("from_participant"))?;
+        let to_participant = parts.next().ok_or(FileNameError::MissingField("to_participant"))?;
+        if parts.next().is_some() {
+            return Err(FileNameError::TooManyFields);
+        }
+
+        let (to_participant, extension) = match to_participant.split_once('.') {
+            Some((to_participant, extension)) => (to_participant, Some(extension.to_owned())),
+            None => (to_participant, None),
+        };
+
+        let mut file_name = FileName::new(format, unique_id, from_participant, to_participant)?;
+        file_name.extension = extension;
+
+        Ok(file_name)
+    }
+
+    pub fn with_extension(mut self, extension: &str) -> Self {
+        self.extension = Some(extension.to_owned());
+        self
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn unique_id(&self) -> &str {
+        &self.unique_id
+    }
+
+    pub fn from_participant(&self) -> &str {
+        &self.from_participant
+    }
+
+    pub fn to_participant(&self) -> &str {
+        &self.to_participant
+    }
+
+    pub fn extension(&self) -> Option<&str> {
+        self.extension.as_deref()
+    }
+
+    /// Checks that the format and participants in the file name agree with the
+    /// file's 100 header record. Participant IDs are compared case-insensitively.
+    pub fn check_header(&self, header: &Header) -> Result<(),FileNameError> {
+        if !self.format.as_str().eq_ignore_ascii_case(header.format()) {
+            return Err(mismatch("format", self.format.as_str(), header.format()));
+        }
+        if !self.from_participant.eq_ignore_ascii_case(header.from_participant()) {
+            return Err(mismatch("from_participant", &self.from_participant, header.from_participant()));
+        }
+        if !self.to_participant.eq_ignore_ascii_case(header.to_participant()) {
+            return Err(mismatch("to_participant", &self.to_participant, header.to_participant()));
+        }
+
+        Ok(())
+    }
+}
+
+fn field<'a>(name: &'static str, value: &'a str, max_length: usize, allowed: fn(char) -> bool) -> Result<&'a str,FileNameError> {
+    if value.is_empty() {
+        Err(FileNameError::MissingField(name))
+    } else if value.len() > max_length || !value.chars().all(allowed) {
+        Err(FileNameError::InvalidField { field: name, value: value.to_owned() })
+    } else {
+        Ok(value)
+    }
+}
+
+fn mismatch(field: &'static str, file_name: &str, header: &str) -> FileNameError {
+    FileNameError::Mismatch { field, file_name: file_name.to_owned(), header: header.to_owned() }
+}
+
+impl fmt::Display for FileName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}#{}#{}", self.format, self.unique_id, self.from_participant, self.to_participant)?;
+        if let Some(extension) = &self.extension {
+            write!(f, ".{}", extension)?;
+        }
+        Ok(())
+    }
+}
+
+impl str::FromStr for FileName {
+    type Err = FileNameError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        FileName::parse(s)
+    }
+}
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum FileNameError {
+    MissingField(&'static str),
+    InvalidField { field: &'static str, value: String },
+    TooManyFields,
+    Mismatch { field: &'static str, file_name: String, header: String },
+}
+
+impl fmt::Display for FileNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileNameError::MissingField(field) => write!(f, "file name is missing {}", field),
+            FileNameError::InvalidField { field, value } => write!(f, "file name has invalid {} '{}'", field, value),
+            FileNameError::TooManyFields => write!(f, "file name has more than four '#' separated fields"),
+            FileNameError::Mismatch { field, file_name, header } => {
+                write!(f, "file name {} '{}' does not match header {} '{}'", field, file_name, field, header)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FileNameError { }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn parse_file_name() {
+        let file_name = FileName::parse("NEM12#SCENARIO605033001#ENERGEXM#NEMMCO.zip").unwrap();
+        assert_eq!(file_name.format(),Format::Nem12);
+        assert_eq!(file_name.unique_id(),"SCENARIO605033001");
+        assert_eq!(file_name.from_participant(),"ENERGEXM");
+        assert_eq!(file_name.to_participant(),"NEMMCO");
+        assert_eq!(file_name.extension(),Some("zip"));
+        assert_eq!(file_name.to_string(),"NEM12#SCENARIO605033001#ENERGEXM#NEMMCO.zip");
+
+        let file_name: FileName = "nem13#SCENARIO11#TCAUSTM#NEMMCO".parse().unwrap();
+        assert_eq!(file_name.format(),Format::Nem13);
+        assert_eq!(file_name.extension(),None);
+        assert_eq!(file_name.to_string(),"NEM13#SCENARIO11#TCAUSTM#NEMMCO");
+
+        assert_eq!(FileName::parse("Example_NEM12_powercor.csv.zip"),Err(FileNameError::InvalidField { field: "format", value: "Example_NEM12_powercor.csv.zip".into() }));
+        assert_eq!(FileName::parse("NEM12#ID#FROM"),Err(FileNameError::MissingField("to_participant")));
+        assert_eq!(FileName::parse("NEM12##FROM#TO"),Err(FileNameError::MissingField("unique_id")));
+        assert_eq!(FileName::parse("NEM12#ID#FROM#TO#X"),Err(FileNameError::TooManyFields));
+        assert!(FileName::parse("NEM12#ID#PARTICIPANT1#TO").is_err());
+    }
+
+    #[test]
+    fn generate_file_name() {
+        let file_name = FileName::new(Format::Nem12, "0123456789", "MDA1", "Ret1").unwrap().with_extension("csv");
+        assert_eq!(file_name.to_string(),"NEM12#0123456789#MDA1#Ret1.csv");
+        assert_eq!(FileName::parse(&file_name.to_string()),Ok(file_name));
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!("nem13".parse::<Format>(),Ok(Format::Nem13));
+        assert_eq!("NEM14".parse::<Format>().unwrap_err().to_string(),"unknown format 'NEM14'");
+    }
+
+    #[test]
+    fn example_file_names() {
+        for dir in ["examples/nem12","examples/nem13"] {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let name = entry.unwrap().file_name().into_string().unwrap();
+                if name.starts_with("Example_") {
+                    continue;
+                }
+                let file_name = FileName::parse(&name).unwrap();
+                assert!(dir.ends_with(&file_name.format().as_str().to_lowercase()));
+            }
+        }
+    }
+
+    #[test]
+    fn check_header() {
+        let created = NaiveDate::from_ymd_opt(2005,5,23).unwrap().and_hms_opt(12,13,0).unwrap();
+        let header = Header::new("NEM13".into(), created, "ETSAMDP".into(), "NEMMCO".into());
+
+        let file_name = FileName::parse("NEM13#Scenario18#ETSAMDP#NEMMCO.zip").unwrap();
+        assert_eq!(file_name.check_header(&header),Ok(()));
+
+        let file_name = FileName::parse("nem13#Scenario18#etsamdp#nemmco.zip").unwrap();
+        assert_eq!(file_name.check_header(&header),Ok(()));
+
+        let file_name = FileName::parse("NEM12#Scenario18#ETSAMDP#NEMMCO.zip").unwrap();
+        assert_eq!(file_name.check_header(&header),Err(mismatch("format","NEM12","NEM13")));
+
+        let file_name = FileName::parse("NEM13#SEN1316103#AGILITY#NEMMCO.zip").unwrap();
+        assert_eq!(file_name.check_header(&header),Err(mismatch("from_participant","AGILITY","ETSAMDP")));
+    }
+}
@@ -1,11 +1,202 @@
 pub mod nem12;
+pub mod nem13;
+pub mod file_name;
+pub mod error;
+pub mod reader;
+pub mod writer;
+pub mod validate;
+pub mod options;
+pub mod time;
 mod common;
-mod codes;
+pub mod codes;
+#[cfg(feature = "zip")]
+pub mod archive;
+
+use nem12::{record::Header, NEM12};
+use nem13::NEM13;
+
+pub use codes::{MethodFlag, Quality, QualityMethod};
+pub use common::{FieldError, Format, Input};
+pub use error::{ParseError, StructureError, Warning, WarningKind};
+pub use file_name::FileName;
+pub use options::{ParseOptions, ValidationProfile};
+pub use reader::Nem12Reader;
+pub use writer::Nem12Writer;
+pub use validate::ValidationError;
+
+/// A parsed MDFF file of either format.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
+pub enum Mdff<'a> {
+    Nem12(NEM12<'a>),
+    Nem13(NEM13<'a>),
+}
+
+impl <'a>Mdff<'a> {
+    pub fn header(&self) -> &Header<'a> {
+        match self {
+            Mdff::Nem12(nem12) => nem12.header(),
+            Mdff::Nem13(nem13) => nem13.header(),
+        }
+    }
+
+    /// Copies all borrowed text out of the source. See [`NEM12::into_owned`].
+    pub fn into_owned(self) -> Mdff<'static> {
+        match self {
+            Mdff::Nem12(nem12) => Mdff::Nem12(nem12.into_owned()),
+            Mdff::Nem13(nem13) => Mdff::Nem13(nem13.into_owned()),
+        }
+    }
+}
+
+/// Parses an MDFF file, reading the format from its 100 header record and
+/// dispatching to the NEM12 or NEM13 grammar.
+pub fn parse(input: &str) -> Result<Mdff<'_>,ParseError> {
+    parse_with(input, &ParseOptions::strict()).map(|(mdff,_)| mdff)
+}
+
+/// Parses an MDFF file of either format with the given options, returning a
+/// warning for each deviation from the specification that was accepted. See
+/// [`nem12::parse_with`] and [`nem13::parse_with`].
+pub fn parse_with<'a>(input: &'a str, options: &ParseOptions) -> Result<(Mdff<'a>,Vec<Warning>),ParseError> {
+    let stripped = common::strip_bom(input);
+    let (_,header) = Header::parse(stripped.into()).map_err(|e| ParseError::from_nom(e, stripped.into(), 1))?;
+
+    match header.format() {
+        "NEM12" => nem12::parse_with(input, options).map(|(nem12,warnings)| (Mdff::Nem12(nem12),warnings)),
+        _ => nem13::parse_with(input, options).map(|(nem13,warnings)| (Mdff::Nem13(nem13),warnings)),
+    }
+}
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn parse_dispatches_on_format() {
+        let nem12 = "100,NEM12,200404201300,MDA1,Ret1\n\
+        200,CCCC123456,E1,001,E1,N1,METSER123,kWh,30,\n\
+        300,20040417,18.023,19.150,17.592,24.155,18.568,22.304,19.222,19.032,19.090,22.237,24.350,22.274,20.193,16.615,19.575,20.391,16.459,20.527,21.438,19.327,21.424,16.656,17.616,18.416,16.666,19.961,18.120,18.023,18.588,21.759,17.841,19.548,18.486,21.391,15.656,16.634,16.377,14.246,17.451,15.742,18.038,18.470,14.936,17.987,15.751,19.750,16.202,14.733,A,,,20040418203500,20040419003500\n\
+        900\n";
+
+        let nem13 = "100,NEM13,200505161145,CNRGYMDP,NEMMCO\n\
+        250,NEM1311002,11,1,11,11,11002,E,38841,20041117093206,A,,,39013,20050217074053,A,,,31,KWH,20050519,20050218104410,\n\
+        900\n";
+
+        assert!(matches!(parse(nem12),Ok(Mdff::Nem12(_))));
+        assert!(matches!(parse(nem13),Ok(Mdff::Nem13(_))));
+        assert_eq!(parse(nem13).unwrap().header().from_participant(),"CNRGYMDP");
+        assert!(parse("100,NEM14,200505161145,CNRGYMDP,NEMMCO\n900\n").is_err());
+    }
+
+    #[test]
+    fn parse_with_dispatches_on_format() {
+        let nem12 = "100,NEM12,200404201300,MDA1,Ret1\n\
+        200,CCCC123456,E1,001,E1,N1,METSER123,kwh,30\n\
+        300,20040417,18.023,19.150,17.592,24.155,18.568,22.304,19.222,19.032,19.090,22.237,24.350,22.274,20.193,16.615,19.575,20.391,16.459,20.527,21.438,19.327,21.424,16.656,17.616,18.416,16.666,19.961,18.120,18.023,18.588,21.759,17.841,19.548,18.486,21.391,15.656,16.634,16.377,14.246,17.451,15.742,18.038,18.470,14.936,17.987,15.751,19.750,16.202,14.733,A,,,20040418203500,20040419003500\n\
+        900\n";
+
+        let nem13 = "100,NEM13,200505161145,CNRGYMDP,NEMMCO\n\
+        250,NEM1311002,11,1,11,11,11002,E,38841,20041117093206,A,,,39013,20050217074053,A,,,31,kwh,20050519,20050218104410\n\
+        900\n";
+
+        let lenient = ParseOptions::lenient();
+        let (mdff,warnings) = parse_with(nem12, &lenient).unwrap();
+        assert!(matches!(mdff,Mdff::Nem12(_)));
+        assert_eq!(warnings,vec![
+            Warning { line: 2, kind: WarningKind::MissingTrailingFields },
+            Warning { line: 2, kind: WarningKind::UomCase },
+        ]);
+        let (mdff,warnings) = parse_with(nem13, &lenient).unwrap();
+        assert!(matches!(mdff,Mdff::Nem13(_)));
+        assert_eq!(warnings,vec![
+            Warning { line: 2, kind: WarningKind::UomCase },
+            Warning { line: 2, kind: WarningKind::MissingTrailingFields },
+        ]);
+
+        assert!(parse_with(nem12, &ParseOptions::strict()).is_err());
+        assert!(parse_with(nem13, &ParseOptions::strict()).is_err());
+        assert!(parse(nem13).is_err());
+    }
+
+    #[test]
+    fn owned_outlives_source() {
+        let source = String::from("100,NEM13,200505161145,CNRGYMDP,NEMMCO\n\
+        250,NEM1311002,11,1,11,11,11002,E,38841,20041117093206,A,,,39013,20050217074053,A,,,31,KWH,20050519,20050218104410,\n\
+        550,N,,E,\n\
+        900\n");
+        let parsed = parse(&source).unwrap();
+        let owned = parsed.clone().into_owned();
+        assert_eq!(owned,parsed);
+        drop(source);
+
+        let handle = std::thread::spawn(move || match owned {
+            Mdff::Nem13(nem13) => nem13.basic_meter_data()[0].nmi.to_string(),
+            Mdff::Nem12(_) => String::new(),
+        });
+        assert_eq!(handle.join().unwrap(),"NEM1311002");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_mdff() {
+        let source = "100,NEM13,200505161145,CNRGYMDP,NEMMCO\n\
+        250,NEM1311002,11,1,11,11,11002,E,38841,20041117093206,A,,,39013,20050217074053,E62,,,31,KWH,20050519,20050218104410,\n\
+        900\n";
+        let mdff = parse(source).unwrap();
+        let json = serde_json::to_value(&mdff).unwrap();
+        assert_eq!(json["NEM13"]["basic_meter_data"][0]["current_quality_method"],"E62");
+        assert_eq!(json["NEM13"]["basic_meter_data"][0]["direction_indicator"],"E");
+        assert_eq!(serde_json::from_value::<Mdff>(json).unwrap(),mdff);
+        assert_eq!(serde_json::to_value(Format::Nem12).unwrap(),"NEM12");
+    }
+
+    fn parse_everything(input: &str) {
+        let _ = parse(input);
+        let _ = nem12::parse(input);
+        let _ = nem12::parse_recovering(input);
+        let _ = nem12::records(input).count();
+        let _ = nem13::parse(input);
+        let _ = parse_with(input, &ParseOptions::lenient());
+        let _ = nem13::parse_with(input, &ParseOptions::lenient());
+        if let Ok(mut reader) = Nem12Reader::new(input.as_bytes()) {
+            while let Some(block) = reader.next_block() {
+                let _ = block;
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_input_never_panics() {
+        let files = [
+            "100,NEM12,200404201300,MDA1,Ret1\n\
+            200,CCCC123456,E1,001,E1,N1,METSER123,kWh,30,20040420\n\
+            300,20040417,18.023,19.150,17.592,24.155,18.568,22.304,19.222,19.032,19.090,22.237,24.350,22.274,20.193,16.615,19.575,20.391,16.459,20.527,21.438,19.327,21.424,16.656,17.616,18.416,16.666,19.961,18.120,18.023,18.588,21.759,17.841,19.548,18.486,21.391,15.656,16.634,16.377,14.246,17.451,15.742,18.038,18.470,14.936,17.987,15.751,19.750,16.202,14.733,V,,,20040418203500,20040419003500\n\
+            400,1,20,F14,76,\n\
+            400,21,48,A,,\n\
+            500,S,RETNSRVCEORD1,20031220154500,001123.5\n\
+            900\n",
+            "100,NEM13,200505161145,CNRGYMDP,NEMMCO\n\
+            250,NEM1311002,11,1,11,11,11002,E,38841,20041117093206,A,,,39013,20050217074053,A,,,31,KWH,20050519,20050218104410,\n\
+            550,N,,A,\n\
+            900\n",
+        ];
+
+        for file in files {
+            for (i,_) in file.char_indices() {
+                parse_everything(&file[..i]);
+                for replacement in ["", ",", "\n", "0", "00", "A", "-", ".", "\u{e9}"] {
+                    parse_everything(&format!("{}{}{}", &file[..i], replacement, &file[i + 1..]));
+                }
+            }
+        }
+
+        parse_everything(&files[0].replace(",30,",",00,"));
+    }
 }
@@ -1,151 +1,441 @@
 use nom::{
-    branch::{alt,permutation}, bytes::complete::tag, character::complete::{alpha1, alphanumeric1, digit1, multispace0}, combinator::{map, opt, peek, recognize}, error::Error, multi::separated_list1 as separated_list, number::complete::double, sequence::{pair, preceded, terminated}, Err, IResult, InputTake, Needed
+    branch::{alt,permutation}, bytes::complete::tag, character::complete::{alphanumeric1, digit1, line_ending}, combinator::{map, map_res, opt, peek, recognize, verify}, error::{make_error, ErrorKind}, sequence::{pair, preceded}, Err
 };
 
 use chrono::{NaiveDateTime,NaiveDate};
-use record::{B2BDetails, EndOfData, Header, IntervalData, IntervalEvent, NMIDataDetails};
-use std::{str, sync::Mutex};
+use record::{Header, NMIDataDetails, MINUTES_PER_DAY};
+use std::str;
 
-use crate::common::*;
+use crate::{codes::QualityMethod, common::*, error::{ParseError, StructureError, Warning, WarningKind}, options::ParseOptions};
 
 #[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NEM12<'a> {
     header: record::Header<'a>,
     nmi_data_details: Vec<record::NMIDataDetails<'a>>
 }
 
-fn parse_nmi_data_details<'a>(input:Input<'a>) -> IResult<Input,NMIDataDetails> {
-    let (input, mut nmi_details) = terminated(NMIDataDetails::parse,rec_separator)(input)?;
-    let interval_data_len = 1440 / nmi_details.interval_length;
-    let (input_pre_b2b,interval_data) = opt(separated_list(rec_separator, parse_interval_data(interval_data_len)))(input)?;
-    let (input,_) = rec_separator(input_pre_b2b)?;
-
-    nmi_details.interval_data_vec = interval_data;
+/// Parses a complete NEM12 file, from the 100 header through to the 900 end of data record.
+///
+/// Lines may end with `\n` or `\r\n`, and a byte order mark before the
+/// header and blank lines between records are skipped.
+pub fn parse(input: &str) -> Result<NEM12<'_>,ParseError> {
+    NEM12::parse(input)
+}
 
-    if let (input,Some(b2b_details)) = opt(separated_list(rec_separator, B2BDetails::parse))(input)? {
-        nmi_details.b2b_details = Some(b2b_details);
-        Ok((input,nmi_details))
-    } else {
-        Ok((input_pre_b2b,nmi_details))
-    }
-    
+/// Parses a complete NEM12 file like [`parse`], also returning a warning for
+/// each byte order mark, blank line or change of line ending it skipped over,
+/// and for each unit of measure written in a nonstandard case.
+pub fn parse_strict(input: &str) -> Result<(NEM12<'_>,Vec<Warning>),ParseError> {
+    NEM12::parse_with(input, &ParseOptions::strict())
 }
 
-fn parse_interval_data<'a>(capacity: usize) -> impl FnMut(Input<'a>) -> IResult<Input<'a>,IntervalData<'a>> {
-    move |input: Input<'a>| {
-        let (input_before_events, mut interval_data) = IntervalData::parse(capacity,input)?;
-        let (input, _) = rec_separator(input_before_events)?;
-        let (input, interval_events) = opt(separated_list(rec_separator, IntervalEvent::parse))(input)?;
-        interval_data.interval_events = interval_events;
+/// Parses a complete NEM12 file with the given options, returning a warning
+/// for each deviation from the specification that was accepted.
+pub fn parse_with<'a>(input: &'a str, options: &ParseOptions) -> Result<(NEM12<'a>,Vec<Warning>),ParseError> {
+    NEM12::parse_with(input, options)
+}
 
-        if interval_data.interval_events.is_some() {
-            Ok((input,interval_data))
-        } else {
-            Ok((input_before_events,interval_data))
+/// Parses the lines of one NMI data details block, a 200 record and the 300,
+/// 400 and 500 records after it, or a lone 900 record, for [`crate::Nem12Reader`].
+/// `first_line` is the line number of the first line in the file, and the
+/// reader has already checked the order of the records.
+pub(crate) fn parse_block(input: &str, first_line: usize, options: ParseOptions) -> (Result<Option<NMIDataDetails<'_>>,ParseError>,Vec<Warning>) {
+    let mut records = records_with(input, options);
+    let mut assembler = Assembler::new(State::NMIDataDetails);
+
+    for record in records.by_ref() {
+        match record {
+            Ok(Record { line, kind }) => assembler.record(line, kind),
+            Err(e) => assembler.error(e),
+        }
+        if !assembler.errors.is_empty() {
+            break;
         }
     }
+    assembler.finish_block();
+
+    let offset = first_line - 1;
+    let warnings = records.warnings.into_iter()
+        .map(|warning| Warning { line: warning.line + offset, ..warning })
+        .collect();
+    match assembler.errors.into_iter().next() {
+        Some(e) => (Err(e.offset_line(offset)),warnings),
+        None => (Ok(assembler.nmi_data_details.pop()),warnings),
+    }
+}
+
+/// A 100 header record for the NEM12 format.
+pub(crate) fn nem12_header(input: Input) -> IResult<Input,Header> {
+    preceded(peek(preceded(tag("100,"), field("format", "NEM12", tag("NEM12")))), Header::parse)(input)
 }
 
 impl <'a>NEM12<'a> {
-    fn new(header: Header<'a>, nmi_data_details: Vec<NMIDataDetails<'a>>) -> Self {
+    pub fn new(header: Header<'a>, nmi_data_details: Vec<NMIDataDetails<'a>>) -> Self {
         NEM12 {
             header,
             nmi_data_details
         }
     }
 
-    fn from_str(input: Input<'a>) -> Result<NEM12<'a>,Err<Error<Input<'a>>>> {
-        let strict = true;
-        let (input,header) = Header::parse(input)?;
-        let (input,_) = rec_separator(input)?;
-        let (input,nmi_data_details) = separated_list(rec_separator, parse_nmi_data_details)(input)?;
-        let (input,_) = rec_separator(input)?;
-        let (_input,_) = EndOfData::parse(input)?;
+    /// Parses a complete NEM12 file. See [`parse`].
+    pub fn parse(input: &'a str) -> Result<NEM12<'a>,ParseError> {
+        Self::parse_with(input, &ParseOptions::strict()).map(|(nem12,_)| nem12)
+    }
 
-        Ok(NEM12 {
-            header,
-            nmi_data_details
-        })
+    /// Parses a complete NEM12 file with the given options. See [`parse_with`].
+    pub fn parse_with(input: &'a str, options: &ParseOptions) -> Result<(NEM12<'a>,Vec<Warning>),ParseError> {
+        let (nem12,errors,mut warnings) = assemble(records_with(input, *options), false);
+        let nem12 = match (nem12,errors.into_iter().next()) {
+            (_,Some(e)) => return Err(e),
+            (Some(nem12),None) => nem12,
+            (None,None) => return Err(ParseError::Structure { line: 1, error: StructureError::MissingHeader }),
+        };
+
+        warnings.extend(layout_warnings(input));
+        warnings.sort_by_key(|warning| warning.line);
+        Ok((nem12,warnings))
+    }
+
+    /// Parses a NEM12 file record by record, skipping to the next line after
+    /// an error so that every problem in the file is reported at once.
+    ///
+    /// Blocks of NMI data details that contain an error are left out of the
+    /// returned `NEM12`, as are the records following a rejected 200 record.
+    /// The `NEM12` is `None` when the 100 header record could not be read.
+    pub fn parse_recovering(input: &'a str) -> (Option<NEM12<'a>>,Vec<ParseError>) {
+        let (nem12,errors,_) = assemble(records(input), true);
+        (nem12,errors)
+    }
+
+    pub fn header(&self) -> &Header<'a> {
+        &self.header
     }
+
+    /// Copies all borrowed text out of the source, so that the result can
+    /// outlive it.
+    pub fn into_owned(self) -> NEM12<'static> {
+        NEM12 {
+            header: self.header.into_owned(),
+            nmi_data_details: self.nmi_data_details.into_iter().map(NMIDataDetails::into_owned).collect(),
+        }
+    }
+
+    pub fn nmi_data_details(&self) -> &[NMIDataDetails<'a>] {
+        &self.nmi_data_details
+    }
+}
+
+/// Iterates a NEM12 file record by record. Created by [`records`].
+///
+/// The interval length of the most recent 200 record is carried forward so
+/// that the following 300 records can be read with the right number of values.
+#[derive(Clone,Debug)]
+pub struct Records<'a> {
+    input: Input<'a>,
+    interval_length: Option<usize>,
+    energy: bool,
+    options: ParseOptions,
+    warnings: Vec<Warning>,
+}
+
+/// A single record and the line it was read from.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Record<'a> {
+    pub line: usize,
+    pub kind: record::Kind<'a>,
 }
 
-struct Parser<'a> {
-    src: Input<'a>,
-    iter: std::iter::Peekable<std::iter::Enumerate<str::Lines<'a>>>,
-    data: Option<NEM12<'a>>,
-    finished: bool,
-    ctx: Mutex<(usize,)>,
-    // ctx: (usize,),
+/// Returns an iterator over the records of a NEM12 file, one per line. A byte
+/// order mark and blank lines are skipped.
+pub fn records(input: &str) -> Records<'_> {
+    records_with(input, ParseOptions::strict())
 }
 
-impl <'a>Parser<'a> {
+/// Returns an iterator over the records of a NEM12 file like [`records`],
+/// reading each record with the given options.
+pub fn records_with(input: &str, options: ParseOptions) -> Records<'_> {
+    Records {
+        input: strip_bom(input).into(),
+        interval_length: None,
+        energy: false,
+        options,
+        warnings: Vec::new(),
+    }
+}
+
+impl <'a>Records<'a> {
+    /// The interval length of the 200 record the iterator is currently within.
+    pub fn interval_length(&self) -> Option<usize> {
+        self.interval_length
+    }
+
+    /// The deviations accepted by a lenient profile in the records read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The number of fields in a record, including its record indicator.
+    fn field_count(&self, indicator: &str) -> Option<usize> {
+        match indicator {
+            "100" | "500" => Some(5),
+            "200" => Some(10),
+            "300" => self.interval_length.and_then(|l| MINUTES_PER_DAY.checked_div(l)).map(|capacity| capacity + 7),
+            "400" => Some(6),
+            "900" => Some(1),
+            _ => None,
+        }
+    }
+
+    fn parse_line(&mut self, mut line: Input<'a>) -> Result<record::Kind<'a>,ParseError> {
+        let first_line = line.location_line() as usize;
+        let lenient = self.options.is_lenient();
+        let field_count = line.get(..3).and_then(|indicator| self.field_count(indicator));
 
-    fn new(src: Input<'a>) -> Self {
-        let parser = Parser {
-            src,
-            iter: src.lines().enumerate().peekable(),
-            data: None,
-            finished: false,
-            ctx:Mutex::new((0,)),
-            // ctx:(0,),
+        if let Some(stripped) = field_count.filter(|_| lenient).and_then(|count| strip_extra_commas(line, count)) {
+            line = stripped;
+            self.warnings.push(Warning { line: first_line, kind: WarningKind::ExtraTrailingCommas });
+        }
+
+        let options = self.options;
+        let res = match line.get(..3) {
+            Some("300") => {
+                let interval_length = self.interval_length
+                    .ok_or(ParseError::Structure { line: first_line, error: StructureError::IntervalDataBeforeDetails })?;
+                let capacity = MINUTES_PER_DAY.checked_div(interval_length).unwrap_or(0);
+                record::interval_data(capacity, self.energy, &options, line).map(|(i,o)| (i,record::Kind::IntervalData(o)))
+            },
+            _ => alt((
+                map(nem12_header, record::Kind::Header),
+                map(|i| record::NMIDataDetails::parse_with(&options, i), record::Kind::NMIDataDetails),
+                map(|i| record::IntervalEvent::parse_with(&options, i), record::Kind::IntervalEvent),
+                map(record::B2BDetails::parse, record::Kind::B2BDetails),
+                map(record::EndOfData::parse, record::Kind::EndOfData),
+            ))(line),
         };
 
-        parser
-    }
-
-    fn parse_line(&mut self) -> Result<Option<record::Kind>,(&'static str,usize,Err<Error<Input<'a>>>)> {
-        if let Some((line_no,line)) = self.iter.next() {
-            // println!("PARSING {}: '{}'",line_no,line);
-            let res = alt((
-                map(record::Header::parse, |o| Some(record::Kind::Header(o))),
-                map(record::NMIDataDetails::parse, |o| {
-                    let mut mutex = self.ctx.lock().unwrap();
-                    mutex.0 = 1440usize / o.interval_length;
-                    Some(record::Kind::NMIDataDetails(o))
-                }),
-                map(|input|record::IntervalData::parse(self.ctx.lock().unwrap().0,input),
-                    |o| { Some(record::Kind::IntervalData(o)) }),
-                map(record::IntervalEvent::parse, |o| Some(record::Kind::IntervalEvent(o))),
-                map(record::B2BDetails::parse, |o| Some(record::Kind::B2BDetails(o))),
-                map(record::EndOfData::parse, |o| Some(record::Kind::EndOfData(o))),
-            ))(line.into())
-                .map_err(|err| {
-                    match err {
-                        Err::Error(sentinel) => ("Error parsing line: ",line_no+1,Err::Error(sentinel)),
-                        Err::Failure(sentinel) => ("Failed to parse line: ",line_no+1,Err::Failure(sentinel)),
-                        Err::Incomplete(needed) => match needed {
-                            Needed::Size(sz) => ("Failed to parse line: ",sz.into(),err),
-                            Needed::Unknown => ("Failed to parse line: ",line_no+1,err)
-                        }
-                    }
-                });
-            match res {
-                Ok(r) => Ok(r.1),
-                Err(e) => Err(e),
+        let (rest,kind) = res.map_err(|e| ParseError::from_nom(e, line, first_line))?;
+        if !rest.is_empty() {
+            return Err(ParseError::from_nom(Err::Error(make_error(rest, ErrorKind::Eof)), line, first_line));
+        }
+
+        if lenient && field_count.is_some_and(|count| line.split(',').count() < count) {
+            self.warnings.push(Warning { line: first_line, kind: WarningKind::MissingTrailingFields });
+        }
+
+        if let record::Kind::NMIDataDetails(nmi_details) = &kind {
+            if uom_in_nonstandard_case(&nmi_details.uom) {
+                self.warnings.push(Warning { line: first_line, kind: WarningKind::UomCase });
+            }
+            self.interval_length = Some(nmi_details.interval_length);
+            self.energy = nmi_details.measures_energy();
+        }
+
+        Ok(kind)
+    }
+}
+
+impl <'a>Iterator for Records<'a> {
+    type Item = Result<Record<'a>,ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = next_line(&mut self.input)?;
+        let line_no = line.location_line() as usize;
+        Some(self.parse_line(line).map(|kind| Record { line: line_no, kind }))
+    }
+}
+
+/// Parses a NEM12 file, carrying on past bad records. See [`NEM12::parse_recovering`].
+pub fn parse_recovering(input: &str) -> (Option<NEM12<'_>>,Vec<ParseError>) {
+    NEM12::parse_recovering(input)
+}
+
+/// The place in a NEM12 file reached after reading a record. Records must
+/// come in the order 100, then for each block a 200 followed by its 300
+/// records, each with their 400 records and any 500 records that follow
+/// them, and finally 900. A block may also have 500 records and no 300
+/// records, but a 300 record can't follow a 500 record that has no 300 record
+/// before it.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub(crate) enum State {
+    Start,
+    Header,
+    NMIDataDetails,
+    IntervalData,
+    IntervalEvent,
+    B2BDetails,
+    B2BDetailsBeforeIntervalData,
+    EndOfData,
+}
+
+impl State {
+    /// Moves past a record with the given record indicator, or reports why
+    /// the record can't come next. Unknown indicators leave the state as it
+    /// is, for the record parsers to reject.
+    pub(crate) fn next(self, record: &str) -> Result<State,StructureError> {
+        match (self, record) {
+            (State::EndOfData, _) => Err(StructureError::RecordAfterEndOfData),
+            (State::Start, "100") => Ok(State::Header),
+            (State::Start, _) => Err(StructureError::MissingHeader),
+            (_, "100") => Err(StructureError::UnexpectedHeader),
+            (_, "200") => Ok(State::NMIDataDetails),
+            (State::Header, "300") => Err(StructureError::IntervalDataBeforeDetails),
+            (State::B2BDetailsBeforeIntervalData, "300") => Err(StructureError::IntervalDataAfterB2BDetails),
+            (_, "300") => Ok(State::IntervalData),
+            (State::IntervalData | State::IntervalEvent, "400") => Ok(State::IntervalEvent),
+            (_, "400") => Err(StructureError::IntervalEventWithoutIntervalData),
+            (State::Header, "500") => Err(StructureError::B2BDetailsBeforeDetails),
+            (State::NMIDataDetails | State::B2BDetailsBeforeIntervalData, "500") => Ok(State::B2BDetailsBeforeIntervalData),
+            (_, "500") => Ok(State::B2BDetails),
+            (State::Header, "900") => Err(StructureError::MissingDetails),
+            (_, "900") => Ok(State::EndOfData),
+            (state, _) => Ok(state),
+        }
+    }
+}
+
+/// Runs the records of a file through the [`State`] machine and an
+/// [`Assembler`], stopping at the first error unless `recover` is set.
+fn assemble(mut records: Records<'_>, recover: bool) -> (Option<NEM12<'_>>,Vec<ParseError>,Vec<Warning>) {
+    let mut assembler = Assembler::new(State::Start);
+    let mut last_line = 0;
+
+    for record in records.by_ref() {
+        match record {
+            Ok(Record { line, kind }) => {
+                last_line = line;
+                assembler.record(line, kind);
+            }
+            Err(e) => {
+                last_line = e.line().unwrap_or(last_line);
+                if let Some(state) = e.record().and_then(|r| assembler.state.next(r).ok()) {
+                    assembler.state = state;
+                }
+                if e.record() == Some("200") {
+                    assembler.finish_block();
+                    assembler.skipping = true;
+                }
+                if !assembler.skipping || e.record() == Some("200") {
+                    assembler.error(e);
+                }
+            }
+        }
+        if !recover && !assembler.errors.is_empty() {
+            return (None,assembler.errors,records.warnings);
+        }
+    }
+
+    match assembler.state {
+        State::EndOfData => {}
+        State::Start => assembler.errors.push(ParseError::Structure { line: last_line + 1, error: StructureError::MissingHeader }),
+        _ => {
+            assembler.finish_block();
+            assembler.errors.push(ParseError::Structure { line: last_line + 1, error: StructureError::MissingEndOfData });
+        }
+    }
+
+    let nem12 = assembler.header.map(|header| NEM12::new(header, assembler.nmi_data_details));
+    (nem12,assembler.errors,records.warnings)
+}
+
+/// Collects records into a `NEM12`, dropping any NMI data details block that
+/// contains an error.
+struct Assembler<'a> {
+    state: State,
+    header: Option<Header<'a>>,
+    nmi_data_details: Vec<NMIDataDetails<'a>>,
+    block: Option<NMIDataDetails<'a>>,
+    block_ok: bool,
+    skipping: bool,
+    errors: Vec<ParseError>,
+}
+
+impl <'a>Assembler<'a> {
+    fn new(state: State) -> Self {
+        Assembler {
+            state,
+            header: None,
+            nmi_data_details: Vec::new(),
+            block: None,
+            block_ok: true,
+            skipping: false,
+            errors: Vec::new(),
+        }
+    }
+
+    fn finish_block(&mut self) {
+        if let Some(block) = self.block.take() {
+            if self.block_ok {
+                self.nmi_data_details.push(block);
+            }
+        }
+        self.skipping = false;
+    }
+
+    fn error(&mut self, e: ParseError) {
+        self.block_ok = false;
+        self.errors.push(e);
+    }
+
+    fn record(&mut self, line: usize, kind: record::Kind<'a>) {
+        let indicator = kind.record_indicator();
+        let state = match self.state.next(indicator) {
+            Err(StructureError::MissingHeader) => {
+                self.error(ParseError::Structure { line, error: StructureError::MissingHeader });
+                State::Header.next(indicator)
             }
+            state => state,
+        };
+        match state {
+            Ok(state) => self.state = state,
+            Err(error) => return self.error(ParseError::Structure { line, error }),
+        }
+
+        if self.skipping && !matches!(kind, record::Kind::NMIDataDetails(_) | record::Kind::EndOfData(_)) {
+            return;
+        }
 
-            // return Ok(Some(res.1));
-        } else {
-            match !self.finished {
-                true => {
-                    self.finished = true;
-                    Ok(None)
+        match kind {
+            record::Kind::Header(header) => self.header = Some(header),
+            record::Kind::NMIDataDetails(nmi_details) => {
+                self.finish_block();
+                self.block = Some(nmi_details);
+                self.block_ok = true;
+            }
+            record::Kind::IntervalData(interval_data) => if let Some(block) = self.block.as_mut() {
+                let interval_data_vec = block.interval_data_vec.get_or_insert_with(Vec::new);
+                // 500 records between two 300 records belong to the first.
+                if let (Some(previous),Some(b2b_details)) = (interval_data_vec.last_mut(),block.b2b_details.take()) {
+                    previous.b2b_details = Some(b2b_details);
+                }
+                interval_data_vec.push(interval_data);
+            },
+            record::Kind::IntervalEvent(interval_event) => {
+                if let Some(interval_data) = self.block.as_mut().and_then(|block| block.interval_data_vec.as_mut()).and_then(|v| v.last_mut()) {
+                    interval_data.interval_events.get_or_insert_with(Vec::new).push(interval_event);
                 }
-                false => Err(("Error: parser consumed all input",0,nom::Err::Incomplete(Needed::Unknown)))
             }
+            record::Kind::B2BDetails(b2b_details) => if let Some(block) = self.block.as_mut() {
+                block.b2b_details.get_or_insert_with(Vec::new).push(b2b_details);
+            },
+            record::Kind::EndOfData(_) => self.finish_block(),
         }
     }
 }
 
+
 pub mod file {
+    #[cfg(test)]
     use super::*;
 
     #[cfg(test)]
     mod tests {
         use super::*;
+        use crate::codes::Quality;
+        use record::{IntervalData, IntervalEvent};
         use pretty_assertions::{assert_eq};
 
-        const MULTIPLE_METERS_STR: &'static str = "100,NEM12,200402070911,MDA1,Ret1\n\
+        const MULTIPLE_METERS_STR: &str = "100,NEM12,200402070911,MDA1,Ret1\n\
         200,NCDE001111,E1B1Q1E2,1,E1,N1,METSER123,Wh,15,\n\
         300,20031204,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,A,,,20031206011132,20031207011022\n\
         300,20031205,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,A,,,20031206011132,20031207011022\n\
@@ -166,12 +456,12 @@ pub mod file {
         300,20031205,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,50,A,,,20031206011155,\n\
         900";
 
-        const DATADETAILS_ROWS_STR: &'static str = "200,NCDE001111,E1B1Q1E2,1,E1,N1,METSER123,Wh,15,\n\
+        const DATADETAILS_ROWS_STR: &str = "200,NCDE001111,E1B1Q1E2,1,E1,N1,METSER123,Wh,15,\n\
         300,20031204,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,A,,,20031206011132,20031207011022\n\
         300,20031205,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,10,A,,,20031206011132,20031207011022\n\
         ";
 
-        const NEM12_WITH_QUALITY: &'static str = "100,NEM12,200404201300,MDA1,Ret1\n\
+        const NEM12_WITH_QUALITY: &str = "100,NEM12,200404201300,MDA1,Ret1\n\
         200,CCCC123456,E1,001,E1,N1,METSER123,kWh,30,\n\
         300,20040417,18.023,19.150,17.592,24.155,18.568,22.304,19.222,19.032,19.090,22.237,24.350,22.274,20.193,16.615,19.575,20.391,16.459,20.527,21.438,19.327,21.424,16.656,17.616,18.416,16.666,19.961,18.120,18.023,18.588,21.759,17.841,19.548,18.486,21.391,15.656,16.634,16.377,14.246,17.451,15.742,18.038,18.470,14.936,17.987,15.751,19.750,16.202,14.733,V,,,20040418203500,20040419003500\n\
         400,1,20,F14,76,\n\
@@ -184,13 +474,19 @@ pub mod file {
         fn get_nmi_data_details_parser() {
             let (input,nmi_data_details) = record::NMIDataDetails::parse(DATADETAILS_ROWS_STR.into()).unwrap();
             
-            let capacity = 1440 / nmi_data_details.interval_length;
-            let (input,_) = preceded(rec_separator,|i|{record::IntervalData::parse(capacity, i)})(input).unwrap();
-            let (input,_) = preceded(rec_separator,|i|{record::IntervalData::parse(capacity, i)})(input).unwrap();
+            let capacity = nmi_data_details.intervals_per_day();
+            let (input,_) = preceded(line_ending,|i|{record::IntervalData::parse(capacity, i)})(input).unwrap();
+            let (input,_) = preceded(line_ending,|i|{record::IntervalData::parse(capacity, i)})(input).unwrap();
             assert_eq!(input.into_fragment(),"\n");
 
-            let (input, nmi_data_details) = parse_nmi_data_details(DATADETAILS_ROWS_STR.into()).unwrap();
-            // TODO: Write test to compare output
+            let (nmi_data_details,warnings) = parse_block(DATADETAILS_ROWS_STR, 2, ParseOptions::strict());
+            let nmi_data_details = nmi_data_details.unwrap().unwrap();
+            assert_eq!(nmi_data_details.interval_data_vec.map(|v| v.len()),Some(2));
+            assert_eq!(warnings,vec![]);
+
+            let invalid = DATADETAILS_ROWS_STR.replacen(",10,",",x,",1);
+            let (res,_) = parse_block(&invalid, 2, ParseOptions::strict());
+            assert_eq!(res.unwrap_err().line(),Some(3));
         }
 
         #[test]
@@ -198,7 +494,7 @@ pub mod file {
             let nmi_data_details = record::NMIDataDetails {
                 nmi: "CCCC123456".into(),
                 nmi_configuration: "E1".into(),
-                register_id: "001".into(),
+                register_id: Some("001".into()),
                 nmi_suffix: "E1".into(),
                 mdm_data_stream_id: Some("N1".into()),
                 meter_serial_number: "METSER123".into(),
@@ -206,60 +502,296 @@ pub mod file {
                 interval_length: 30usize,
                 next_scheduled_read_date: None,
                 interval_data_vec: Some(vec![IntervalData {
-                    interval_date: NaiveDate::from_ymd(2004,04,17),
+                    interval_date: NaiveDate::from_ymd_opt(2004,4,17).unwrap(),
                     interval_value: vec![18.023, 19.15, 17.592, 24.155, 18.568, 22.304, 19.222, 19.032, 19.09, 22.237, 24.35, 22.274, 20.193, 16.615, 19.575, 20.391, 16.459, 20.527, 21.438, 19.327, 21.424, 16.656, 17.616, 18.416, 16.666, 19.961, 18.12, 18.023, 18.588, 21.759, 17.841, 19.548, 18.486, 21.391, 15.656, 16.634, 16.377, 14.246, 17.451, 15.742, 18.038, 18.47, 14.936, 17.987, 15.751, 19.75, 16.202, 14.733],
-                    quality_method: "V".into(),
+                    quality_method: QualityMethod::new(Quality::V, None).unwrap(),
                     reason_code: None,
                     reason_description: None,
-                    update_datetime: NaiveDateTime::parse_from_str("2004-04-18T20:35:00","%Y-%m-%dT%H:%M:%S").unwrap(),
+                    update_datetime: Some(NaiveDateTime::parse_from_str("2004-04-18T20:35:00","%Y-%m-%dT%H:%M:%S").unwrap()),
                     msats_load_datetime: Some(NaiveDateTime::parse_from_str("2004-04-19T00:35:00","%Y-%m-%dT%H:%M:%S").unwrap()),
                     interval_events: Some(vec![
                         IntervalEvent::parse("400,1,20,F14,76,\n".into()).map(|(_,o)|o).unwrap(),
                         IntervalEvent::parse("400,21,24,A,,\n".into()).map(|(_,o)|o).unwrap(),
                         IntervalEvent::parse("400,25,48,S14,1,\n".into()).map(|(_,o)|o).unwrap(),
-                    ])
+                    ]),
+                    b2b_details: None,
                 }]),
                 b2b_details: None,
             };
             let nem12_test = NEM12 {
                 header: record::Header::new(
                     "NEM12".into(),
-                    NaiveDate::from_ymd(2004,04,20).and_hms(13,0,0),
+                    NaiveDate::from_ymd_opt(2004,4,20).unwrap().and_hms_opt(13,0,0).unwrap(),
                     "MDA1".into(),
                     "Ret1".into()
                 ),
                 nmi_data_details: vec![nmi_data_details],
             };
-            let nem12_obj = NEM12::from_str(NEM12_WITH_QUALITY.into());
-            assert_eq!(Ok(nem12_test),nem12_obj)
+            let nem12_obj = NEM12::parse(NEM12_WITH_QUALITY);
+            assert_eq!(nem12_test,nem12_obj.unwrap())
+        }
+
+        #[test]
+        fn public_parse() {
+            let nem12_obj = crate::nem12::parse(MULTIPLE_METERS_STR).unwrap();
+            assert_eq!(nem12_obj.header().format(),"NEM12");
+            assert_eq!(nem12_obj.header().created(),NaiveDate::from_ymd_opt(2004,2,7).unwrap().and_hms_opt(9,11,0).unwrap());
+            assert_eq!(nem12_obj.header().from_participant(),"MDA1");
+            assert_eq!(nem12_obj.header().to_participant(),"Ret1");
+            assert_eq!(nem12_obj.nmi_data_details().len(),6);
+            assert_eq!(nem12_obj.nmi_data_details()[5].nmi,"NDDD001888");
+
+            let nem13_header = MULTIPLE_METERS_STR.replacen("NEM12","NEM13",1);
+            assert!(NEM12::parse(&nem13_header).is_err());
+        }
+
+        #[test]
+        fn into_owned() {
+            let source = MULTIPLE_METERS_STR.to_owned();
+            let nem12 = crate::nem12::parse(&source).unwrap();
+            let owned: NEM12<'static> = nem12.clone().into_owned();
+            assert_eq!(owned,nem12);
+            drop(nem12);
+            drop(source);
+
+            assert_eq!(owned.header().from_participant(),"MDA1");
+            assert_eq!(owned.nmi_data_details()[5].nmi,"NDDD001888");
+            assert!(matches!(owned.nmi_data_details()[5].nmi,std::borrow::Cow::Owned(_)));
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn serde_json_shape() {
+            let input = "100,NEM12,200405011135,MDA1,Ret1\n\
+            200,VABD000163,E1Q1,1,E1,N1,METSER123,kWh,30,20040501\n\
+            300,20040201,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,0,V,,,20040202120025,\n\
+            400,1,20,F14,76,\n\
+            400,21,48,A,,\n\
+            500,S,RETNSRVCEORD1,20031220154500,001123.5\n\
+            900\n";
+            let nem12 = crate::nem12::parse(input).unwrap();
+            let json = serde_json::to_value(&nem12).unwrap();
+
+            assert_eq!(json["header"],serde_json::json!({
+                "format": "NEM12",
+                "created": "2004-05-01T11:35:00",
+                "from_participant": "MDA1",
+                "to_participant": "Ret1",
+            }));
+            let details = &json["nmi_data_details"][0];
+            assert_eq!((&details["uom"],&details["interval_length"],&details["next_scheduled_read_date"]),(&"kWh".into(),&30.into(),&"2004-05-01".into()));
+            let interval_data = &details["interval_data_vec"][0];
+            assert_eq!((&interval_data["interval_value"][0],&interval_data["quality_method"],&interval_data["msats_load_datetime"]),(&1.111.into(),&"V".into(),&serde_json::Value::Null));
+            assert_eq!(interval_data["interval_events"][0],serde_json::json!({
+                "start_interval": 1,
+                "end_interval": 20,
+                "quality_method": "F14",
+                "reason_code": "76",
+                "reason_description": null,
+            }));
+            assert_eq!(details["b2b_details"][0]["read_datetime"],"2003-12-20T15:45:00");
+
+            let back: NEM12<'static> = serde_json::from_str(&json.to_string()).unwrap();
+            assert_eq!(back,nem12);
+            assert!(serde_json::from_str::<NEM12>(&json.to_string().replace("F14","F99")).is_err());
         }
 
         #[test]
         fn multiple_meters_from_str() {
-            let _nem12_obj = NEM12::from_str(MULTIPLE_METERS_STR.into()).unwrap();
+            let _nem12_obj = NEM12::parse(MULTIPLE_METERS_STR).unwrap();
         }
 
         #[test]
         fn multiple_meters() {
+            let records = crate::nem12::records(MULTIPLE_METERS_STR).collect::<Result<Vec<_>,_>>().unwrap();
 
-            let mut parser = Parser::new(MULTIPLE_METERS_STR.into());
+            assert_eq!(records.len(),20);
+            assert!(matches!(records[0].kind,record::Kind::Header(_)));
+            assert!(matches!(records[1].kind,record::Kind::NMIDataDetails(_)));
+            assert!(matches!(records[2].kind,record::Kind::IntervalData(_)));
+            assert_eq!(records[19],Record { line: 20, kind: record::Kind::EndOfData(record::EndOfData{}) });
+        }
 
-            for _ in 1..20 {
-                parser.parse_line().unwrap();
-            }
+        #[test]
+        fn records_with_events() {
+            let mut records = crate::nem12::records(NEM12_WITH_QUALITY);
+            assert_eq!(records.interval_length(),None);
+
+            let kinds = records.by_ref().map(|r| r.unwrap()).map(|r| (r.line,r.kind)).collect::<Vec<_>>();
+            assert_eq!(kinds.len(),7);
+            assert_eq!(records.interval_length(),Some(30));
+            assert_eq!(kinds[3],(4,record::Kind::IntervalEvent(IntervalEvent::parse("400,1,20,F14,76,".into()).unwrap().1)));
+            assert!(records.next().is_none());
+        }
 
-            assert_eq!(parser.parse_line(),Ok(Some(record::Kind::EndOfData(record::EndOfData{}))));
-            assert_eq!(parser.parse_line(),Ok(None));
-            assert_eq!(parser.parse_line(),Err(("Error: parser consumed all input",0,nom::Err::Incomplete(Needed::Unknown))));
+        #[test]
+        fn b2b_details_between_interval_data() {
+            let interval_data = NEM12_WITH_QUALITY.lines().nth(2).unwrap();
+            let b2b_details = "500,S,RETNSRVCEORD1,20031220154500,001123.5";
+            let input = NEM12_WITH_QUALITY.replace("900\n",&format!("{}\n{}\n{}\n900\n",b2b_details,interval_data.replace("20040417","20040418"),b2b_details.replace("S,","E,")));
+            let nem12 = NEM12::parse(&input).unwrap();
+
+            let details = &nem12.nmi_data_details()[0];
+            let interval_data_vec = details.interval_data_vec.as_deref().unwrap();
+            assert_eq!(interval_data_vec.len(),2);
+            assert_eq!(interval_data_vec[0].b2b_details.as_deref().unwrap()[0].trans_code,"S");
+            assert_eq!(interval_data_vec[1].b2b_details,None);
+            assert_eq!(details.b2b_details.as_deref().unwrap()[0].trans_code,"E");
         }
 
         #[test]
-        fn nem12_from_str() {
-            let _nem12_obj = NEM12::from_str(MULTIPLE_METERS_STR.into());
-            if let Err(e) = _nem12_obj {
-                println!("{:?}",e);
+        fn records_errors() {
+            let input = MULTIPLE_METERS_STR.replace("200,NCDE001111,E1B1Q1E2,2,B1,N1,METSER123,Wh,15,","200,NCDE001111,E1B1Q1E2,2,B1,N1,METSER123,Wh,XX,");
+            let errors = crate::nem12::records(&input).filter_map(|r| r.err()).collect::<Vec<_>>();
+            assert_eq!(errors.len(),1);
+            assert_eq!(errors[0].line(),Some(5));
+
+            let input = MULTIPLE_METERS_STR.replacen("200,NCDE001111,E1B1Q1E2,1,E1,N1,METSER123,Wh,15,\n","",1);
+            let mut records = crate::nem12::records(&input);
+            assert!(records.next().unwrap().is_ok());
+            assert!(matches!(records.next(),Some(Err(ParseError::Structure { line: 2, .. }))));
+
+            let input = MULTIPLE_METERS_STR.replacen("MDA1,Ret1","MDA1,Ret1,",1);
+            let mut records = crate::nem12::records(&input);
+            assert!(matches!(records.next(),Some(Err(ParseError::Record { record: Some("100"), line: 1, column: 33, .. }))));
+        }
+
+        #[test]
+        fn parse_recovering() {
+            let input = MULTIPLE_METERS_STR
+                .replace("300,20031205,10,10","300,2003120X,10,10")
+                .replacen("300,20031204,50,50","300,20031204,5O,50",1);
+            let (nem12,errors) = crate::nem12::parse_recovering(&input);
+            assert_eq!(errors.iter().map(|e| (e.line(),e.field())).collect::<Vec<_>>(),vec![
+                (Some(4),Some("interval_date")),
+                (Some(7),Some("interval_date")),
+                (Some(9),Some("interval_value")),
+            ]);
+
+            let expected = crate::nem12::parse(MULTIPLE_METERS_STR).unwrap();
+            let nem12 = nem12.unwrap();
+            assert_eq!(nem12.header(),expected.header());
+            assert_eq!(nem12.nmi_data_details(),&expected.nmi_data_details()[3..]);
+
+            let input = MULTIPLE_METERS_STR
+                .replace("200,NCDE001111,E1B1Q1E2,1,E1,N1,METSER123,Wh,15,","200,NCDE001111,E1B1Q1E2,1,E1,N1,METSER123,Wh,XX,")
+                .replace("\n900","");
+            let (nem12,errors) = crate::nem12::parse_recovering(&input);
+            assert_eq!(errors.len(),2);
+            assert_eq!(errors[0].field(),Some("interval_length"));
+            assert!(matches!(errors[1],ParseError::Structure { line: 20, .. }));
+            assert_eq!(nem12.unwrap().nmi_data_details(),&expected.nmi_data_details()[1..]);
+
+            let (nem12,errors) = crate::nem12::parse_recovering("200,NCDE001111,E1B1Q1E2,1,E1,N1,METSER123,Wh,15,\n900");
+            assert!(nem12.is_none());
+            assert!(matches!(errors[..],[ParseError::Structure { line: 1, .. }]));
+        }
+
+        #[test]
+        fn line_layout() {
+            use crate::error::{Warning, WarningKind};
+            let warnings = |line: usize, kind: WarningKind| Warning { line, kind };
+
+            let crlf = include_str!("../examples/unzipped/Example_NEM12_actual_interval.csv");
+            let lf = crlf.replace("\r\n","\n");
+            let expected = crate::nem12::parse(&lf).unwrap();
+            assert_eq!(crate::nem12::parse_strict(crlf).unwrap(),(expected.clone(),vec![]));
+            assert_eq!(
+                crate::nem12::parse_strict(include_str!("../examples/unzipped/Example_NEM12_byte_order_mark.csv")).unwrap(),
+                (expected,vec![warnings(1,WarningKind::ByteOrderMark)]),
+            );
+
+            let expected = crate::nem12::parse(include_str!("../examples/unzipped/Example_NEM12_multiple_meters.csv")).unwrap();
+            let input = include_str!("../examples/unzipped/Example_NEM12_blank_lines.csv");
+            assert_eq!(
+                crate::nem12::parse_strict(input).unwrap(),
+                (expected,[5,9,13,17,21,26].into_iter().map(|line| warnings(line,WarningKind::BlankLine)).collect()),
+            );
+            assert!(crate::nem12::records(input).all(|r| r.is_ok()));
+
+            let expected = crate::nem12::parse(include_str!("../examples/unzipped/Example_NEM12_multiple_quality.csv")).unwrap();
+            assert_eq!(
+                crate::nem12::parse_strict(include_str!("../examples/unzipped/Example_NEM12_mixed_line_endings.csv")).unwrap(),
+                (expected,[4,5,6].into_iter().map(|line| warnings(line,WarningKind::MixedLineEndings)).collect()),
+            );
+
+            assert!(crate::nem12::parse(&MULTIPLE_METERS_STR.replace("900","900 ")).is_err());
+            assert!(crate::nem12::parse(&MULTIPLE_METERS_STR.replace("\n200","\r200")).is_err());
+        }
+
+        #[test]
+        fn validation_profiles() {
+            use crate::error::{Warning, WarningKind};
+            use crate::options::ParseOptions;
+            let lenient = |input| crate::nem12::parse_with(input, &ParseOptions::lenient());
+
+            let body = include_str!("../examples/unzipped/Example_NEM12_powercor_missing_fields.csv");
+            let input = format!("100,NEM12,200402070911,MDA1,Ret1{}\n", &body[..body.find("900").unwrap() + 3]);
+            let err = crate::nem12::parse(&input).unwrap_err();
+            assert_eq!((err.line(),err.record()),(Some(3),Some("300")));
+            let (nem12,warnings) = lenient(&input).unwrap();
+            assert_eq!(warnings,vec![
+                Warning { line: 3, kind: WarningKind::MissingTrailingFields },
+                Warning { line: 5, kind: WarningKind::MissingTrailingFields },
+            ]);
+            let interval_data = &nem12.nmi_data_details()[1].interval_data_vec.as_ref().unwrap()[0];
+            assert_eq!(interval_data.interval_value[0],2.222);
+            assert_eq!((interval_data.update_datetime,interval_data.msats_load_datetime),(None,None));
+
+            let input = MULTIPLE_METERS_STR.replace(",VArh,",",varh,");
+            let uom_warnings = vec![Warning { line: 8, kind: WarningKind::UomCase },Warning { line: 17, kind: WarningKind::UomCase }];
+            let (nem12,warnings) = crate::nem12::parse_strict(&input).unwrap();
+            assert_eq!(nem12.nmi_data_details()[2].uom,"varh");
+            assert_eq!(warnings,uom_warnings);
+            assert_eq!(lenient(&input).unwrap(),(nem12,uom_warnings));
+            assert_eq!(crate::nem12::parse_strict(&MULTIPLE_METERS_STR.replace(",VArh,",",VARH,")).unwrap().1,vec![]);
+
+            let input = MULTIPLE_METERS_STR.replacen("MDA1,Ret1","MDA1,Ret1,,",1).replacen("\n900","\n900,",1);
+            assert!(crate::nem12::parse(&input).is_err());
+            let (nem12,warnings) = lenient(&input).unwrap();
+            assert_eq!(nem12,crate::nem12::parse(MULTIPLE_METERS_STR).unwrap());
+            assert_eq!(warnings,vec![Warning { line: 1, kind: WarningKind::ExtraTrailingCommas },Warning { line: 20, kind: WarningKind::ExtraTrailingCommas }]);
+
+            let input = NEM12_WITH_QUALITY.replace("400,1,20,F14,76,","400,1,20,F14,76").replace("400,21,24,A,,","400,21,24,A");
+            assert!(crate::nem12::parse(&input).is_err());
+            let (nem12,warnings) = lenient(&input).unwrap();
+            assert_eq!(nem12,crate::nem12::parse(NEM12_WITH_QUALITY).unwrap());
+            assert_eq!(warnings.iter().map(|w| w.line).collect::<Vec<_>>(),vec![4,5]);
+
+            assert!(lenient(&MULTIPLE_METERS_STR.replacen("MDA1,Ret1","MDA1,Ret1,x",1)).is_err());
+        }
+
+        /// Example files from MDPs that write units such as `kvarh` parse in
+        /// either profile, with a warning for each 200 record.
+        #[cfg(feature = "zip")]
+        #[test]
+        fn uom_case_in_examples() {
+            use crate::error::WarningKind;
+            use crate::options::ParseOptions;
+
+            for path in [
+                "examples/nem12/NEM12#SCENARIO305032701#ENERGEXM#NEMMCO.zip",
+                "examples/nem12/NEM12#03050_05031001#WBAYM#NEMMCO.zip",
+                "examples/nem12/nem12#SCENARIO07NEM1206103#ELECTDSM#NEMMCO.zip",
+            ] {
+                let archive = crate::archive::Archive::open(path).unwrap();
+                let contents = archive.entries()[0].contents();
+                for options in [ParseOptions::strict(),ParseOptions::lenient()] {
+                    let (_,warnings) = crate::nem12::parse_with(contents, &options).unwrap();
+                    assert!(warnings.iter().any(|w| w.kind == WarningKind::UomCase),"{}",path);
+                }
+                assert!(crate::parse(contents).is_ok(),"{}",path);
             }
-            // _nem12_obj.unwrap();
+        }
+
+        #[test]
+        fn nem12_from_str() {
+            let nem12 = NEM12::parse(MULTIPLE_METERS_STR).unwrap();
+            let details = nem12.nmi_data_details();
+
+            assert_eq!(details.iter().map(|d| d.nmi_suffix.as_ref()).collect::<Vec<_>>(),["E1","B1","Q1","E2","B1","K2"]);
+            assert!(details.iter().all(|d| d.interval_data_vec.as_ref().map(Vec::len) == Some(2)));
+            assert_eq!(details[3].interval_data_vec.as_ref().unwrap()[0].interval_value[0],100.0);
         }
     }
 }
@@ -267,7 +799,16 @@ pub mod file {
 pub mod record {
     use nom::multi::many_m_n;
 
+    use std::{borrow::Cow, fmt};
+
+    use chrono::{DateTime, Duration, FixedOffset, NaiveTime, Utc};
+
     use super::*;
+    use crate::codes::{CodeError, Quality, Uom};
+    use crate::options::ParseOptions;
+    use crate::time::market_datetime;
+    #[cfg(feature = "tz")]
+    use crate::time::Jurisdiction;
 
     #[derive(Clone,Debug,PartialEq)]
     pub enum Kind<'a> {
@@ -279,28 +820,32 @@ pub mod record {
         EndOfData(EndOfData)
     }
 
+    impl Kind<'_> {
+        /// The record indicator that starts this kind of record, e.g. `"300"`.
+        pub fn record_indicator(&self) -> &'static str {
+            match self {
+                Kind::Header(_) => "100",
+                Kind::NMIDataDetails(_) => "200",
+                Kind::IntervalData(_) => "300",
+                Kind::IntervalEvent(_) => "400",
+                Kind::B2BDetails(_) => "500",
+                Kind::EndOfData(_) => "900",
+            }
+        }
+    }
+
     // Header record (100)
-    #[derive(Clone,Debug)]
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Header<'a> {
-        format: Input<'a>,
+        format: Cow<'a,str>,
         created: NaiveDateTime,
-        from_participant: Input<'a>,
-        to_participant: Input<'a>,
-    }
-
-    impl <'a>PartialEq for Header<'a> {
-        fn eq(&self, other: &Self) -> bool {
-            self.format.into_fragment() == other.format.into_fragment() &&
-            self.created == other.created &&
-            self.from_participant.into_fragment() == other.from_participant.into_fragment() &&
-            self.to_participant.into_fragment() == other.to_participant.into_fragment()
-        }
+        from_participant: Cow<'a,str>,
+        to_participant: Cow<'a,str>,
     }
 
-    impl <'a>Eq for Header<'a> { }
-
     impl <'a>Header<'a> {
-        pub fn new(format:Input<'a>, created: NaiveDateTime, from_participant: Input<'a>, to_participant: Input<'a>) -> Self {
+        pub fn new(format: Cow<'a,str>, created: NaiveDateTime, from_participant: Cow<'a,str>, to_participant: Cow<'a,str>) -> Self {
             Header {
                 format,
                 created,
@@ -309,52 +854,87 @@ pub mod record {
             }
         }
 
+        pub fn format(&self) -> &str {
+            &self.format
+        }
+
+        pub fn created(&self) -> NaiveDateTime {
+            self.created
+        }
+
+        pub fn from_participant(&self) -> &str {
+            &self.from_participant
+        }
+
+        pub fn to_participant(&self) -> &str {
+            &self.to_participant
+        }
+
+        pub fn into_owned(self) -> Header<'static> {
+            Header {
+                format: owned(self.format),
+                created: self.created,
+                from_participant: owned(self.from_participant),
+                to_participant: owned(self.to_participant),
+            }
+        }
+
         pub fn parse(input: Input) -> IResult<Input,Header> {
             let (input, _) = tag("100,")(input)?;
-            let (input, format) = alt((tag("NEM12"),tag("NEM13")))(input)?;
+            let (input, format) = field("format", "NEM12 or NEM13", alt((tag("NEM12"),tag("NEM13"))))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, created) = datetime_12(input)?;
+            let (input, created) = field("created", "date and time as YYYYMMDDhhmm", datetime_12)(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, from_participant) = section_of_max_length(alphanumeric1,10)(input)?;
+            let (input, from_participant) = field("from_participant", "up to 10 characters", section_of_max_length(varchar,10))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, to_participant) = section_of_max_length(alphanumeric1,10)(input)?;
+            let (input, to_participant) = field("to_participant", "up to 10 characters", section_of_max_length(varchar,10))(input)?;
     
             let header = Header::new(
-                format,
+                text(format),
                 created,
-                from_participant,
-                to_participant
+                text(from_participant),
+                text(to_participant)
             );
     
             Ok((input,header))
         }
     }
 
+    pub(crate) const MINUTES_PER_DAY: usize = 1440;
+
+    /// The interval lengths in minutes that a 200 record may give. Each divides
+    /// the day evenly, from 1440 values a day for 1 minute intervals down to 48
+    /// for 30 minute intervals.
+    pub const INTERVAL_LENGTHS: [usize; 5] = [1, 5, 10, 15, 30];
+
     // NMI data details record (200)
     #[derive(Clone,Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct NMIDataDetails<'a> {
-        pub nmi: Input<'a>,
-        pub nmi_configuration: Input<'a>,
-        pub register_id: Input<'a>,
-        pub nmi_suffix: Input<'a>,
-        pub mdm_data_stream_id: Option<Input<'a>>,
-        pub meter_serial_number: Input<'a>,
-        pub uom: Input<'a>,
+        pub nmi: Cow<'a,str>,
+        pub nmi_configuration: Cow<'a,str>,
+        pub register_id: Option<Cow<'a,str>>,
+        pub nmi_suffix: Cow<'a,str>,
+        pub mdm_data_stream_id: Option<Cow<'a,str>>,
+        pub meter_serial_number: Cow<'a,str>,
+        pub uom: Cow<'a,str>,
         pub interval_length: usize,
         pub next_scheduled_read_date: Option<NaiveDate>,
         pub interval_data_vec: Option<Vec<IntervalData<'a>>>,
+        /// The 500 records after the block's last 300 record, or all of them
+        /// in a block without 300 records. See [`IntervalData::b2b_details`].
         pub b2b_details: Option<Vec<B2BDetails<'a>>>,
     }
 
     impl <'a>PartialEq for NMIDataDetails<'a> {
         fn eq(&self, other: &Self) -> bool {
-            self.nmi.into_fragment() == other.nmi.into_fragment() &&
-            self.nmi_configuration.into_fragment() == other.nmi_configuration.into_fragment() &&
-            self.register_id.into_fragment() == other.register_id.into_fragment() &&
-            self.nmi_suffix.into_fragment() == other.nmi_suffix.into_fragment() &&
-            self.mdm_data_stream_id.map(|o| o.into_fragment()) == other.mdm_data_stream_id.map(|o| o.into_fragment()) &&
-            self.meter_serial_number.into_fragment() == other.meter_serial_number.into_fragment() &&
-            self.uom.into_fragment().to_owned().to_lowercase() == other.uom.into_fragment().to_owned().to_lowercase() &&
+            self.nmi == other.nmi &&
+            self.nmi_configuration == other.nmi_configuration &&
+            self.register_id == other.register_id &&
+            self.nmi_suffix == other.nmi_suffix &&
+            self.mdm_data_stream_id == other.mdm_data_stream_id &&
+            self.meter_serial_number == other.meter_serial_number &&
+            self.uom.eq_ignore_ascii_case(&other.uom) &&
             self.interval_length == other.interval_length &&
             self.next_scheduled_read_date == other.next_scheduled_read_date &&
             self.interval_data_vec == other.interval_data_vec &&
@@ -365,37 +945,78 @@ pub mod record {
     impl <'a>Eq for NMIDataDetails<'a> { }
 
     impl <'a>NMIDataDetails<'a> {
-        pub fn parse(input: Input) -> IResult<Input,NMIDataDetails> {
+        pub fn into_owned(self) -> NMIDataDetails<'static> {
+            NMIDataDetails {
+                nmi: owned(self.nmi),
+                nmi_configuration: owned(self.nmi_configuration),
+                register_id: self.register_id.map(owned),
+                nmi_suffix: owned(self.nmi_suffix),
+                mdm_data_stream_id: self.mdm_data_stream_id.map(owned),
+                meter_serial_number: owned(self.meter_serial_number),
+                uom: owned(self.uom),
+                interval_length: self.interval_length,
+                next_scheduled_read_date: self.next_scheduled_read_date,
+                interval_data_vec: self.interval_data_vec.map(|v| v.into_iter().map(IntervalData::into_owned).collect()),
+                b2b_details: self.b2b_details.map(|v| v.into_iter().map(B2BDetails::into_owned).collect()),
+            }
+        }
+
+        /// Whether the data stream's unit of measure is a known energy unit,
+        /// whose interval values can't be negative. See [`Uom::is_energy`].
+        pub fn measures_energy(&self) -> bool {
+            self.uom.parse::<Uom>().is_ok_and(|uom| uom.is_energy())
+        }
+
+        /// The number of values in each 300 record of this NMI data stream.
+        pub fn intervals_per_day(&self) -> usize {
+            MINUTES_PER_DAY.checked_div(self.interval_length).unwrap_or(0)
+        }
+
+        /// Resamples every day of the data stream to `interval_length` minutes,
+        /// summing energy and averaging other units. See [`Aggregation::for_uom`].
+        pub fn resample(&self, interval_length: usize) -> Result<Vec<Interval<'_>>,ResampleError> {
+            let uom = self.uom.parse::<Uom>().map_err(ResampleError::Uom)?;
+            self.resample_with(interval_length, Aggregation::for_uom(uom))
+        }
+
+        /// Resamples every day of the data stream to `interval_length` minutes
+        /// with the given aggregation, such as [`Aggregation::Max`] for peak demand.
+        pub fn resample_with(&self, interval_length: usize, aggregation: Aggregation) -> Result<Vec<Interval<'_>>,ResampleError> {
+            let mut intervals = Vec::new();
+            for interval_data in self.interval_data_vec.iter().flatten() {
+                intervals.extend(interval_data.resample(interval_length, aggregation)?);
+            }
+            Ok(intervals)
+        }
+
+        pub fn parse(input: Input<'a>) -> IResult<Input<'a>,NMIDataDetails<'a>> {
+            Self::parse_with(&ParseOptions::strict(), input)
+        }
+
+        /// Parses a 200 record, allowing the deviations of a lenient profile.
+        /// See [`ParseOptions`].
+        pub fn parse_with(options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,NMIDataDetails<'a>> {
             let (input, _) = tag("200,")(input)?;
-            let (input, nmi) = section_of_exact_length(alphanumeric1, 10)(input)?;
-            let (input, _) = tag(",")(input)?;
-            let (input, nmi_configuration) = section_of_max_length(alphanumeric1, 240)(input)?;
+            let (input, nmi) = field("nmi", "10 alphanumeric characters", section_of_exact_length(alphanumeric1, 10))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, register_id) = section_of_max_length(alphanumeric1, 10)(input)?;
+            let (input, nmi_configuration) = field("nmi_configuration", "up to 240 alphanumeric characters", section_of_max_length(alphanumeric1, 240))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, nmi_suffix) = section_of_exact_length(alphanumeric1, 2)(input)?;
+            let (input, register_id) = field("register_id", "up to 10 characters or nothing", opt(section_of_max_length(varchar, 10)))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, mdm_data_stream_id) = optional_field(section_of_exact_length(alphanumeric1, 2),",")(input)?;
+            let (input, nmi_suffix) = field("nmi_suffix", "2 alphanumeric characters", section_of_exact_length(alphanumeric1, 2))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, meter_serial_number) = section_of_max_length(alphanumeric1, 12)(input)?;
+            let (input, mdm_data_stream_id) = field("mdm_data_stream_id", "2 alphanumeric characters or nothing", optional_field(section_of_exact_length(alphanumeric1, 2),","))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, uom) = section_of_max_length(alphanumeric1, 5)(input)?;
+            let (input, meter_serial_number) = field("meter_serial_number", "up to 12 characters", section_of_max_length(varchar, 12))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, interval_length) = section_of_exact_length(digit1, 2)(input).map(|(input,val)| (input,val.parse::<usize>().unwrap()))?;
+            let (input, uom) = field("uom", "up to 5 alphanumeric characters", section_of_max_length(alphanumeric1, 5))(input)?;
             let (input, _) = tag(",")(input)?;
-            let (input, next_scheduled_read_date) = match date_8(input){
-                Ok(d) => Ok((d.0,Some(d.1))),
-                Err(nom::Err::Error(_)) => {
-                    match peek(alt((eof,tag("\n"))))(input) { // TODO: Add alt(eof,tag) to optional_field
-                        Ok((input,_)) => Ok((input,None)),
-                        Err(nom::Err::Error(e)) => {
-                            return Err(nom::Err::Error(e))
-                        }
-                        x => { println!("'{:?}'", x); panic!("This should never happen") }
-                    }
-                },
-                x => { println!("{:?}", x); panic!("This should never happen") }
-            }?;
+            let (input, interval_length) = field("interval_length", "interval length of 01, 05, 10, 15 or 30 minutes", verify(map_res(section_of_exact_length(digit1, 2), |val: Input| val.parse::<usize>()), |l| INTERVAL_LENGTHS.contains(l)))(input)?;
+            let (input, next_scheduled_read_date) = trailing_field(options.is_lenient(), field("next_scheduled_read_date", "date as YYYYMMDD or nothing", |input| match date_8(input) {
+                Ok((input,date)) => Ok((input,Some(date))),
+                Err(nom::Err::Error(_)) => peek(alt((eof,line_ending)))(input).map(|(input,_)| (input,None)),
+                Err(e) => Err(e),
+            }))(input)?;
     
             // let interval_data_length = 1440usize / interval_length;
             // let (input, interval_data_vec) = separated_list0(
@@ -404,13 +1025,13 @@ pub mod record {
             // )(input)?;
     
             let nmi_data_details  = NMIDataDetails {
-                nmi,
-                nmi_configuration,
-                register_id,
-                nmi_suffix,
-                mdm_data_stream_id,
-                meter_serial_number,
-                uom,
+                nmi: text(nmi),
+                nmi_configuration: text(nmi_configuration),
+                register_id: register_id.map(text),
+                nmi_suffix: text(nmi_suffix),
+                mdm_data_stream_id: mdm_data_stream_id.map(text),
+                meter_serial_number: text(meter_serial_number),
+                uom: text(uom),
                 interval_length,
                 next_scheduled_read_date,
                 interval_data_vec: None,
@@ -422,43 +1043,287 @@ pub mod record {
     }
 
     // Interval data record (300)
-    #[derive(Clone,Debug)]
+    #[derive(Clone,Debug,PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct IntervalData<'a> {
         pub interval_date: NaiveDate,
         pub interval_value: Vec<f64>,
-        pub quality_method: Input<'a>,
-        pub reason_code: Option<Input<'a>>,
-        pub reason_description: Option<Input<'a>>,
-        pub update_datetime: NaiveDateTime,
+        pub quality_method: QualityMethod,
+        pub reason_code: Option<Cow<'a,str>>,
+        pub reason_description: Option<Cow<'a,str>>,
+        /// Always present in a file that follows the specification, but may be
+        /// missing from one read with [`crate::ValidationProfile::Lenient`].
+        pub update_datetime: Option<NaiveDateTime>,
         pub msats_load_datetime: Option<NaiveDateTime>,
-        pub interval_events: Option<Vec<IntervalEvent<'a>>>
-    }
-
-    impl <'a>PartialEq for IntervalData<'a> {
-        fn eq(&self, other: &Self) -> bool {
-            self.interval_date == other.interval_date &&
-            self.interval_value == other.interval_value &&
-            self.quality_method.into_fragment() == other.quality_method.into_fragment() &&
-            self.reason_code.map(|o| o.into_fragment()) == other.reason_code.map(|o| o.into_fragment()) &&
-            self.reason_description.map(|o| o.into_fragment()) == other.reason_description.map(|o| o.into_fragment()) &&
-            self.update_datetime == other.update_datetime &&
-            self.msats_load_datetime == other.msats_load_datetime &&
-            self.interval_events == other.interval_events
-        }
+        pub interval_events: Option<Vec<IntervalEvent<'a>>>,
+        /// The 500 records between this record's 400 records and the next 300
+        /// record of the block.
+        pub b2b_details: Option<Vec<B2BDetails<'a>>>,
     }
 
     impl <'a>Eq for IntervalData<'a> { }
 
     impl <'a>IntervalData<'a> {
+        /// Parses a 300 record with `capacity` interval values.
+        ///
+        /// The unit of measure comes from the 200 record, so negative values
+        /// are accepted here. Parsing a whole file rejects them for energy units.
         pub fn parse(capacity: usize, input: Input<'a>) -> IResult<Input<'a>,IntervalData<'a>> {
-            interval_data(capacity, input)
+            interval_data(capacity, false, &ParseOptions::strict(), input)
+        }
+
+        /// Parses a 300 record, allowing the deviations of a lenient profile.
+        /// See [`ParseOptions`].
+        pub fn parse_with(capacity: usize, options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,IntervalData<'a>> {
+            interval_data(capacity, false, options, input)
+        }
+
+        pub fn into_owned(self) -> IntervalData<'static> {
+            IntervalData {
+                interval_date: self.interval_date,
+                interval_value: self.interval_value,
+                quality_method: self.quality_method,
+                reason_code: self.reason_code.map(owned),
+                reason_description: self.reason_description.map(owned),
+                update_datetime: self.update_datetime,
+                msats_load_datetime: self.msats_load_datetime,
+                interval_events: self.interval_events.map(|v| v.into_iter().map(IntervalEvent::into_owned).collect()),
+                b2b_details: self.b2b_details.map(|v| v.into_iter().map(B2BDetails::into_owned).collect()),
+            }
+        }
+
+        /// Returns the quality, method and reason of each interval value. Days
+        /// of variable (`V`) quality take them from their 400 records, which
+        /// must cover every interval exactly once; other days apply the 300
+        /// record's own to every interval and must not have 400 records.
+        pub fn interval_qualities(&self) -> Result<Vec<IntervalQuality<'_>>,IntervalEventError> {
+            let len = self.interval_value.len();
+            let events = self.interval_events.as_deref().unwrap_or_default();
+
+            if self.quality_method.quality() != Quality::V {
+                if !events.is_empty() {
+                    return Err(IntervalEventError::EventsWithoutVariableQuality);
+                }
+                let quality = IntervalQuality {
+                    quality_method: self.quality_method,
+                    reason_code: self.reason_code.as_deref(),
+                    reason_description: self.reason_description.as_deref(),
+                };
+                return Ok(vec![quality; len]);
+            }
+
+            if events.is_empty() {
+                return Err(IntervalEventError::MissingEvents);
+            }
+
+            let mut qualities = vec![None; len];
+            for event in events {
+                if event.quality_method.quality() == Quality::V {
+                    return Err(IntervalEventError::VariableEvent { start: event.start_interval });
+                }
+                if event.start_interval < 1 || event.end_interval < event.start_interval || event.end_interval > len {
+                    return Err(IntervalEventError::OutOfRange { start: event.start_interval, end: event.end_interval });
+                }
+
+                for (i,slot) in qualities.iter_mut().enumerate().take(event.end_interval).skip(event.start_interval - 1) {
+                    if slot.is_some() {
+                        return Err(IntervalEventError::Overlap { interval: i + 1 });
+                    }
+                    *slot = Some(IntervalQuality {
+                        quality_method: event.quality_method,
+                        reason_code: event.reason_code.as_deref(),
+                        reason_description: event.reason_description.as_deref(),
+                    });
+                }
+            }
+
+            qualities.into_iter()
+                .enumerate()
+                .map(|(i,quality)| quality.ok_or(IntervalEventError::Gap { interval: i + 1 }))
+                .collect()
+        }
+
+        /// Returns each interval value with its timestamps and quality. The
+        /// interval length is the length of the day divided by the number of
+        /// values, and interval `n` ends `n` interval lengths after midnight
+        /// market time at the start of the interval date.
+        pub fn intervals(&self) -> Result<impl Iterator<Item = Interval<'_>>,IntervalEventError> {
+            let qualities = self.interval_qualities()?;
+            let length = Duration::minutes(MINUTES_PER_DAY.checked_div(self.interval_value.len()).unwrap_or(0) as i64);
+            let midnight = market_datetime(self.interval_date.and_time(NaiveTime::MIN));
+
+            Ok(self.interval_value.iter().zip(qualities).enumerate().map(move |(i,(value,quality))| {
+                let start = midnight + length * i as i32;
+                Interval { start, end: start + length, value: *value, quality }
+            }))
+        }
+
+        /// Combines the intervals of the day into intervals of `interval_length`
+        /// minutes, which must be a multiple of the current interval length.
+        pub fn resample(&self, interval_length: usize, aggregation: Aggregation) -> Result<Vec<Interval<'_>>,ResampleError> {
+            let from = MINUTES_PER_DAY.checked_div(self.interval_value.len()).unwrap_or(0);
+            if !INTERVAL_LENGTHS.contains(&interval_length) || !interval_length.is_multiple_of(from) {
+                return Err(ResampleError::Length { from, to: interval_length });
+            }
+
+            let intervals = self.intervals()
+                .map_err(|error| ResampleError::IntervalEvents { date: self.interval_date, error })?
+                .collect::<Vec<_>>();
+            Ok(resample(&intervals, interval_length / from, aggregation))
         }
     }
 
-    fn interval_data<'a>(capacity: usize, input: Input<'a>) -> IResult<Input<'a>,IntervalData<'a>> {
+    /// The quality, method and reason that apply to a single interval value.
+    #[derive(Clone,Copy,Debug,PartialEq,Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    pub struct IntervalQuality<'a> {
+        pub quality_method: QualityMethod,
+        pub reason_code: Option<&'a str>,
+        pub reason_description: Option<&'a str>,
+    }
+
+    /// A single interval value. `start` and `end` are in market time, see
+    /// [`MARKET_TIME`](crate::time::MARKET_TIME).
+    #[derive(Clone,Copy,Debug,PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    pub struct Interval<'a> {
+        pub start: DateTime<FixedOffset>,
+        pub end: DateTime<FixedOffset>,
+        pub value: f64,
+        pub quality: IntervalQuality<'a>,
+    }
+
+    impl <'a>Interval<'a> {
+        pub fn start_utc(&self) -> DateTime<Utc> {
+            self.start.with_timezone(&Utc)
+        }
+
+        pub fn end_utc(&self) -> DateTime<Utc> {
+            self.end.with_timezone(&Utc)
+        }
+
+        /// The start of the interval in local time, daylight saving included.
+        #[cfg(feature = "tz")]
+        pub fn start_local(&self, jurisdiction: Jurisdiction) -> DateTime<chrono_tz::Tz> {
+            self.start.with_timezone(&jurisdiction.tz())
+        }
+
+        /// The end of the interval in local time, daylight saving included.
+        #[cfg(feature = "tz")]
+        pub fn end_local(&self, jurisdiction: Jurisdiction) -> DateTime<chrono_tz::Tz> {
+            self.end.with_timezone(&jurisdiction.tz())
+        }
+    }
+
+    /// How values are combined when resampling to a longer interval length.
+    #[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum Aggregation {
+        Sum,
+        Mean,
+        Max,
+    }
+
+    impl Aggregation {
+        /// Energy is summed over the longer interval, while demand, voltage,
+        /// current and power factor are averaged.
+        pub fn for_uom(uom: Uom) -> Self {
+            if uom.is_energy() { Aggregation::Sum } else { Aggregation::Mean }
+        }
+
+        fn apply(&self, values: impl Iterator<Item = f64>) -> f64 {
+            match self {
+                Aggregation::Sum => values.sum(),
+                Aggregation::Mean => {
+                    let (sum,count) = values.fold((0.0,0),|(sum,count),v| (sum + v,count + 1));
+                    if count == 0 { 0.0 } else { sum / count as f64 }
+                }
+                Aggregation::Max => values.fold(f64::NAN,f64::max),
+            }
+        }
+    }
+
+    /// Combines consecutive intervals into intervals `factor` times as long,
+    /// taking the worst quality of each group.
+    fn resample<'a>(intervals: &[Interval<'a>], factor: usize, aggregation: Aggregation) -> Vec<Interval<'a>> {
+        intervals.chunks(factor).filter_map(|group| {
+            let (first,last) = (group.first()?,group.last()?);
+            let worst = group.iter()
+                .map(|i| i.quality)
+                .reduce(|worst,q| if q.quality_method.quality().rank() > worst.quality_method.quality().rank() { q } else { worst })?;
+            Some(Interval {
+                start: first.start,
+                end: last.end,
+                value: aggregation.apply(group.iter().map(|i| i.value)),
+                quality: worst,
+            })
+        }).collect()
+    }
+
+    /// The reason a day or data stream can't be resampled.
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    pub enum ResampleError {
+        /// The target length is not one of [`INTERVAL_LENGTHS`] or not a multiple of the current length.
+        Length { from: usize, to: usize },
+        /// The unit of measure is unknown, so the aggregation can't be chosen.
+        Uom(CodeError),
+        IntervalEvents { date: NaiveDate, error: IntervalEventError },
+    }
+
+    impl fmt::Display for ResampleError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ResampleError::Length { from, to } => write!(f, "can't resample {} minute intervals to {} minutes", from, to),
+                ResampleError::Uom(e) => write!(f, "can't choose an aggregation: {}", e),
+                ResampleError::IntervalEvents { date, error } => write!(f, "{}: {}", date, error),
+            }
+        }
+    }
+
+    impl std::error::Error for ResampleError { }
+
+    /// A problem with the 400 records of a 300 record. Intervals are numbered from 1.
+    #[derive(Clone,Copy,Debug,PartialEq,Eq)]
+    pub enum IntervalEventError {
+        EventsWithoutVariableQuality,
+        MissingEvents,
+        VariableEvent { start: usize },
+        OutOfRange { start: usize, end: usize },
+        Overlap { interval: usize },
+        Gap { interval: usize },
+    }
+
+    impl fmt::Display for IntervalEventError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                IntervalEventError::EventsWithoutVariableQuality => write!(f, "400 records follow a 300 record whose quality is not V"),
+                IntervalEventError::MissingEvents => write!(f, "300 record of V quality has no 400 records"),
+                IntervalEventError::VariableEvent { start } => write!(f, "400 record from interval {} has V quality", start),
+                IntervalEventError::OutOfRange { start, end } => write!(f, "400 record covers intervals {} to {}, outside the day", start, end),
+                IntervalEventError::Overlap { interval } => write!(f, "interval {} is covered by more than one 400 record", interval),
+                IntervalEventError::Gap { interval } => write!(f, "interval {} is not covered by any 400 record", interval),
+            }
+        }
+    }
+
+    impl std::error::Error for IntervalEventError { }
+
+    /// Parses one interval value: a decimal number of up to 15 characters and
+    /// 5 decimal places, which can only be negative when `energy` is unset.
+    fn interval_value<'a>(energy: bool) -> impl FnMut(Input<'a>) -> IResult<Input<'a>,f64> {
+        let number = field("interval_value", "decimal number", decimal);
+        let length = field("interval_value", "decimal number of up to 15 characters", verify(number, |n: &Input| n.len() <= MAX_NUMERIC_LENGTH));
+        let precision = field("interval_value", "decimal number with up to 5 decimal places", verify(length, |n: &Input| decimal_places(n) <= MAX_DECIMAL_PLACES));
+        let value = map_res(precision, |n: Input| n.parse::<f64>());
+        field("interval_value", "value of 0 or more for an energy unit", verify(value, move |v: &f64| !energy || *v >= 0.0))
+    }
+
+    /// Parses a 300 record. `energy` is set when the unit of the 200 record
+    /// measures energy, ruling out negative values.
+    pub(crate) fn interval_data<'a>(capacity: usize, energy: bool, options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,IntervalData<'a>> {
         let (input, _) = tag("300,")(input)?;
-        let (input, interval_date) = date_8(input)?;
-        let (input, interval_value) = many_m_n(capacity,capacity,preceded(tag(","),double))(input)?;
+        let (input, interval_date) = field("interval_date", "date as YYYYMMDD", date_8)(input)?;
+        let (input, interval_value) = many_m_n(capacity,capacity,preceded(tag(","),interval_value(energy)))(input)?;
 
         // if let Some(details) = nmi_data_details_rec {
         //     if (1440 / details.interval_length) != interval_value.len() {
@@ -467,17 +1332,12 @@ pub mod record {
         // }
 
         let (input, _) = tag(",")(input)?;
-        let (input, quality_method) = section_of_max_length(alpha1, 3)(input)?;
-        let (input, _) = tag(",")(input)?;
-        let (input, reason_code) = optional_field(section_of_max_length(digit1, 3),",")(input)?;
-
-        let (input, _) = tag(",")(input)?;
-        let (input, reason_description) = optional_field(section_of_max_length(alphanumeric1, 240),",")(input)?;
-
-        let (input, _) = tag(",")(input)?;
-        let (input, update_datetime) = datetime_14(input)?;
-        let (input, _) = tag(",")(input)?;
-        let (input, msats_load_datetime) = opt(datetime_14)(input)?;
+        let (input, quality_method) = field("quality_method", "quality flag, with a method flag for E, F and S", quality_method)(input)?;
+        let lenient = options.is_lenient();
+        let (input, reason_code) = trailing_field(lenient, field("reason_code", "up to 3 digits or nothing", opt(section_of_max_length(digit1, 3))))(input)?;
+        let (input, reason_description) = trailing_field(lenient, field("reason_description", "up to 240 characters or nothing", opt(section_of_max_length(varchar, 240))))(input)?;
+        let (input, update_datetime) = trailing_field(lenient, map(field("update_datetime", "date and time as YYYYMMDDhhmmss", datetime_14), Some))(input)?;
+        let (input, msats_load_datetime) = trailing_field(lenient, opt(datetime_14))(input)?;
 
         // // Get Event Codes (400 recs)
         // let (input,_) = rec_separator(input)?;
@@ -487,110 +1347,114 @@ pub mod record {
             interval_date,
             interval_value,
             quality_method,
-            reason_code,
-            reason_description,
+            reason_code: reason_code.map(text),
+            reason_description: reason_description.map(text),
             update_datetime,
             msats_load_datetime,
-            interval_events: None
+            interval_events: None,
+            b2b_details: None,
         };
 
         Ok((input,interval_data))
     }
 
     // Interval event record (400)
-    #[derive(Clone,Debug)]
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct IntervalEvent<'a> {
-        pub start_interval: Input<'a>,
-        pub end_interval: Input<'a>,
-        pub quality_method: Input<'a>,
-        pub reason_code: Option<Input<'a>>,
-        pub reason_description: Option<Input<'a>>,
+        pub start_interval: usize,
+        pub end_interval: usize,
+        pub quality_method: QualityMethod,
+        pub reason_code: Option<Cow<'a,str>>,
+        pub reason_description: Option<Cow<'a,str>>,
     }
 
-    impl <'a>PartialEq for IntervalEvent<'a> {
-        fn eq(&self, other: &Self) -> bool {
-            self.start_interval.into_fragment() == other.start_interval.into_fragment() &&
-            self.end_interval.into_fragment() == other.end_interval.into_fragment() &&
-            self.quality_method.into_fragment() == other.quality_method.into_fragment() &&
-            self.reason_code.map(|o| o.into_fragment()) == other.reason_code.map(|o| o.into_fragment()) &&
-            self.reason_description.map(|o| o.into_fragment()) == other.reason_description.map(|o| o.into_fragment())
+    impl <'a>IntervalEvent<'a> {
+        pub fn parse(input: Input<'a>) -> IResult<Input<'a>,IntervalEvent<'a>> {
+            interval_event(&ParseOptions::strict(), input)
         }
-    }
 
-    impl <'a>Eq for IntervalEvent<'a> { }
+        /// Parses a 400 record, allowing the deviations of a lenient profile.
+        /// See [`ParseOptions`].
+        pub fn parse_with(options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,IntervalEvent<'a>> {
+            interval_event(options, input)
+        }
 
-    impl <'a>IntervalEvent<'a> {
-        pub fn parse(input: Input<'a>) -> IResult<Input<'a>,IntervalEvent> {
-            interval_event(input)
+        pub fn into_owned(self) -> IntervalEvent<'static> {
+            IntervalEvent {
+                start_interval: self.start_interval,
+                end_interval: self.end_interval,
+                quality_method: self.quality_method,
+                reason_code: self.reason_code.map(owned),
+                reason_description: self.reason_description.map(owned),
+            }
         }
     }
 
-    fn interval_event<'a>(input: Input<'a>) -> IResult<Input<'a>,IntervalEvent<'a>> {
+    fn interval_event<'a>(options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,IntervalEvent<'a>> {
         let (input, _) = tag("400,")(input)?;
-        let (input, start_interval) = section_of_max_length(digit1,4)(input)?;
-        let (input, _) = tag(",")(input)?;
-        let (input, end_interval) = section_of_max_length(digit1,4)(input)?;
+        let (input, start_interval) = field("start_interval", "up to 4 digits", map_res(section_of_max_length(digit1,4), |s: Input| s.parse::<usize>()))(input)?;
         let (input, _) = tag(",")(input)?;
-        let (input, quality_method) = section_of_max_length(alphanumeric1,3)(input)?;
+        let (input, end_interval) = field("end_interval", "up to 4 digits", map_res(section_of_max_length(digit1,4), |s: Input| s.parse::<usize>()))(input)?;
         let (input, _) = tag(",")(input)?;
-        let (input, reason_code) = optional_field(section_of_max_length(digit1,3),",")(input)?;
-        let (input, _) = tag(",")(input)?;
-        let (input, reason_description) = optional_field(section_of_max_length(alphanumeric1,24),"\n")(input)?;
+        let (input, quality_method) = field("quality_method", "quality flag, with a method flag for E, F and S", quality_method)(input)?;
+        let lenient = options.is_lenient();
+        let (input, reason_code) = trailing_field(lenient, field("reason_code", "up to 3 digits or nothing", opt(section_of_max_length(digit1,3))))(input)?;
+        let (input, reason_description) = trailing_field(lenient, field("reason_description", "up to 240 characters or nothing", opt(section_of_max_length(varchar,240))))(input)?;
 
         let interval_event = IntervalEvent {
             start_interval,
             end_interval,
             quality_method,
-            reason_code,
-            reason_description
+            reason_code: reason_code.map(text),
+            reason_description: reason_description.map(text)
         };
 
         Ok((input,interval_event))
     }
 
     // B2B details record (500)
-    #[derive(Clone,Debug)]
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct B2BDetails<'a> {
-        pub trans_code: Input<'a>,
-        pub ret_service_order: Input<'a>,
-        pub read_datetime: NaiveDateTime,
-        pub index_read: Input<'a>,
-    }
-
-    impl <'a>PartialEq for B2BDetails<'a> {
-        fn eq(&self, other: &Self) -> bool {
-            self.trans_code.into_fragment() == other.trans_code.into_fragment() &&
-            self.ret_service_order.into_fragment() == other.ret_service_order.into_fragment() &&
-            self.read_datetime == other.read_datetime &&
-            self.index_read.into_fragment() == other.index_read.into_fragment()
-        }
+        pub trans_code: Cow<'a,str>,
+        pub ret_service_order: Option<Cow<'a,str>>,
+        pub read_datetime: Option<NaiveDateTime>,
+        pub index_read: Option<Cow<'a,str>>,
     }
 
-    impl <'a>Eq for B2BDetails<'a> { }
-
     impl B2BDetails<'_> {
         pub fn parse(input: Input) -> IResult<Input,B2BDetails> {
             b2b_details(input)
         }
+
+        pub fn into_owned(self) -> B2BDetails<'static> {
+            B2BDetails {
+                trans_code: owned(self.trans_code),
+                ret_service_order: self.ret_service_order.map(owned),
+                read_datetime: self.read_datetime,
+                index_read: self.index_read.map(owned),
+            }
+        }
     }
 
     fn b2b_details<'a>(input: Input<'a>) -> IResult<Input<'a>,B2BDetails<'a>> {
         let (input, _) = tag("500,")(input)?;
-        let (input, trans_code) = section_of_exact_length(alpha1,1)(input)?;
+        let (input, trans_code) = field("trans_code", "transaction code A, C, G, D, E, N, O, S or R", transaction_code)(input)?;
         let (input, _) = tag(",")(input)?;
-        let (input, ret_service_order) = section_of_max_length(alphanumeric1,15)(input)?;
+        let (input, ret_service_order) = field("ret_service_order", "up to 15 characters or nothing", opt(section_of_max_length(varchar,15)))(input)?;
         let (input, _) = tag(",")(input)?;
-        let (input, read_datetime) = datetime_14(input)?;
+        let (input, read_datetime) = field("read_datetime", "date and time as YYYYMMDDhhmmss or nothing", opt(datetime_14))(input)?;
         let (input, _) = tag(",")(input)?;
-        let (input, index_read) = section_of_max_length(
+        let (input, index_read) = field("index_read", "register read of up to 15 characters or nothing", opt(section_of_max_length(
             move |i| recognize(permutation((digit1,opt(pair(tag("."),digit1)))))(i)
-        ,15)(input)?;
+        ,15)))(input)?;
 
         let b2b_details = B2BDetails {
-            trans_code,
-            ret_service_order,
+            trans_code: text(trans_code),
+            ret_service_order: ret_service_order.map(text),
             read_datetime,
-            index_read
+            index_read: index_read.map(text)
         };
 
         Ok((input,b2b_details))
@@ -598,6 +1462,7 @@ pub mod record {
 
     // End of data (900)
     #[derive(Clone,Debug,PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct EndOfData {}
 
     impl EndOfData {
@@ -608,24 +1473,22 @@ pub mod record {
 
     fn end_of_data(input: Input) -> IResult<Input,EndOfData> {
         let (input, _) = tag("900")(input)?;
-        let (input, _) = multispace0(input)?; // TODO: Should this be removed?
         Ok((input,EndOfData {}))
     }
 
     #[cfg(test)]
     mod tests {
-        use std::borrow::Borrow;
-
-        use super::{record,Input};
+        use super::record;
+        use crate::codes::{MethodFlag, Quality, QualityMethod};
         use nom::error;
         use chrono::{NaiveDate};
     
         #[test]
         fn header_100() {
-            let date = NaiveDate::from_ymd(2004,5,1).and_hms(11, 35, 0);
+            let date = NaiveDate::from_ymd_opt(2004,5,1).unwrap().and_hms_opt(11, 35, 0).unwrap();
             let header = record::Header::new (
                 "NEM12".into(),
-                date.clone(),
+                date,
                 "MDA1".into(),
                 "Ret1".into()
             );
@@ -640,7 +1503,7 @@ pub mod record {
     
             let header = record::Header::new (
                 "NEM12".into(),
-                date.clone().into(),
+                date,
                 "0123456789".into(),
                 "Ret1".into()
             );
@@ -661,9 +1524,9 @@ pub mod record {
             let res = match record::Header::parse(raw.into())
             .map(|(r,v)| (r.into_fragment(),v)) {
                 Ok(o) => { println!("{:?}",o); panic!("Failed") },
-                Err(nom::Err::Error(e)) => { (e.input.into_fragment(),error::ErrorKind::Verify) }, //NOTE: must accomodate custom errors somehow
+                Err(nom::Err::Failure(e)) => { (e.input.into_fragment(),e.code) },
                 Err(nom::Err::Incomplete(_)) |
-                Err(nom::Err::Failure(_)) => panic!("This should never happen")
+                Err(nom::Err::Error(_)) => panic!("This should never happen")
             };
     
             assert_eq!(res, ("12345678910,Ret1\n",error::ErrorKind::Verify));
@@ -674,7 +1537,7 @@ pub mod record {
             let nmi_data_details = record::NMIDataDetails {
                 nmi: "VABD000163".into(),
                 nmi_configuration: "E1Q1".into(),
-                register_id: "1".into(),
+                register_id: Some("1".into()),
                 nmi_suffix: "E1".into(),
                 mdm_data_stream_id: Some("N1".into()),
                 meter_serial_number: "METSER123".into(),
@@ -701,20 +1564,101 @@ pub mod record {
                     nom::Err::Error(e) => nom::Err::Error(e.input.into_fragment()),
                     nom::Err::Failure(e) => nom::Err::Failure(e.input.into_fragment()),
                 }
-            }),Err(nom::Err::Error("1234\n")));
+            }),Err(nom::Err::Failure("1234\n")));
+
+            let raw = "200,VABD000163,E1Q1,1,E1,N1,METSER123,kWh,00,\n";
+
+            let res = record::NMIDataDetails::parse(raw.into());
+
+            assert!(matches!(res,Err(nom::Err::Failure(crate::FieldError { field: Some("interval_length"), .. }))));
         }
     
+        #[test]
+        fn interval_lengths() {
+            for (length,ok) in [("01",true),("1",false),("5",false),("05",true),("10",true),("15",true),("30",true),("07",false),("45",false),("60",false),("005",false)] {
+                let raw = format!("200,VABD000163,E1Q1,1,E1,N1,METSER123,kWh,{},\n", length);
+                let res = record::NMIDataDetails::parse(raw.as_str().into());
+                assert_eq!(res.is_ok(),ok,"{}",length);
+            }
+
+            let err = crate::nem12::parse("100,NEM12,200405011135,MDA1,Ret1\n200,VABD000163,E1Q1,1,E1,N1,METSER123,kWh,45,\n900\n").unwrap_err();
+            assert_eq!(err.to_string(),"line 2, column 43, 200 record, field interval_length: expected interval length of 01, 05, 10, 15 or 30 minutes, found '45'");
+        }
+
+        #[test]
+        fn five_minute_intervals() {
+            let nem12 = crate::nem12::parse(include_str!("../examples/unzipped/Example_NEM12_5_minute_interval.csv")).unwrap();
+            let details = &nem12.nmi_data_details()[0];
+            assert_eq!((details.interval_length,details.intervals_per_day()),(5,288));
+
+            let days = details.interval_data_vec.as_ref().unwrap();
+            assert_eq!(days.len(),2);
+            assert!(days.iter().all(|day| day.interval_value.len() == 288));
+            assert_eq!(crate::validate::nem12(&nem12),vec![]);
+
+            let intervals = days[1].intervals().unwrap().collect::<Vec<_>>();
+            assert_eq!(intervals[0].end.to_rfc3339(),"2021-09-30T00:05:00+10:00");
+            assert_eq!(intervals[144].start.to_rfc3339(),"2021-09-30T12:00:00+10:00");
+            assert_eq!(intervals[144].quality.quality_method.to_string(),"E52");
+            assert_eq!(intervals[287].end.to_rfc3339(),"2021-10-01T00:00:00+10:00");
+        }
+
+        #[test]
+        fn fifteen_minute_intervals() {
+            let input = include_str!("../examples/unzipped/Example_NEM12_15_minute_interval.csv");
+            let nem12 = crate::nem12::parse(input).unwrap();
+            assert_eq!(nem12.nmi_data_details().len(),2);
+            for details in nem12.nmi_data_details() {
+                assert_eq!(details.intervals_per_day(),96);
+                let day = &details.interval_data_vec.as_ref().unwrap()[0];
+                assert_eq!(day.intervals().unwrap().last().unwrap().start.to_rfc3339(),"2021-09-30T23:45:00+10:00");
+            }
+
+            let err = crate::nem12::parse(&input.replace(",kWh,15,",",kWh,30,")).unwrap_err();
+            assert_eq!(err.line(),Some(3));
+        }
+
+        #[test]
+        fn resample() {
+            let nem12 = crate::nem12::parse(include_str!("../examples/unzipped/Example_NEM12_5_minute_interval.csv")).unwrap();
+            let details = &nem12.nmi_data_details()[0];
+
+            let intervals = details.resample(30).unwrap();
+            assert_eq!(intervals.len(),96);
+            assert_eq!(intervals[0].start.to_rfc3339(),"2021-09-29T00:00:00+10:00");
+            assert_eq!(intervals[0].end.to_rfc3339(),"2021-09-29T00:30:00+10:00");
+            assert!((intervals[0].value - 0.75).abs() < 1e-9);
+            assert_eq!(intervals[48+23].quality.quality_method.to_string(),"A");
+            assert_eq!(intervals[48+24].quality.quality_method.to_string(),"E52");
+            assert_eq!(intervals[48+25].quality.quality_method.to_string(),"A");
+
+            let day = &details.interval_data_vec.as_ref().unwrap()[0];
+            let mean = day.resample(30, record::Aggregation::Mean).unwrap();
+            let max = day.resample(30, record::Aggregation::Max).unwrap();
+            assert!((mean[0].value - 0.125).abs() < 1e-9);
+            assert!((max[0].value - 0.15).abs() < 1e-9);
+            assert_eq!(day.resample(5, record::Aggregation::Sum).unwrap().len(),288);
+            assert_eq!(record::Aggregation::for_uom(crate::codes::Uom::Kilowatt),record::Aggregation::Mean);
+
+            assert_eq!(details.resample(45),Err(record::ResampleError::Length { from: 5, to: 45 }));
+            let nem12 = crate::nem12::parse(include_str!("../examples/unzipped/Example_NEM12_15_minute_interval.csv")).unwrap();
+            assert_eq!(nem12.nmi_data_details()[0].resample(10),Err(record::ResampleError::Length { from: 15, to: 10 }));
+            assert_eq!(nem12.nmi_data_details()[0].resample(5),Err(record::ResampleError::Length { from: 15, to: 5 }));
+            assert_eq!(nem12.nmi_data_details()[1].resample(30).unwrap()[14].value,1.5);
+        }
+
         #[test]
         fn interval_data_300() {
             let interval_data = record::IntervalData {
-                interval_date: NaiveDate::from_ymd(2004, 2, 1),
+                interval_date: NaiveDate::from_ymd_opt(2004, 2, 1).unwrap(),
                 interval_value: vec![1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111],
-                quality_method: "A".into(),
+                quality_method: QualityMethod::new(Quality::A, None).unwrap(),
                 reason_code: None,
                 reason_description: None,
-                update_datetime: NaiveDate::from_ymd(2004, 2, 2).and_hms(12, 0, 25),
-                msats_load_datetime: Some(NaiveDate::from_ymd(2004, 2, 2).and_hms(14, 25, 16)),
+                update_datetime: Some(NaiveDate::from_ymd_opt(2004, 2, 2).unwrap().and_hms_opt(12, 0, 25).unwrap()),
+                msats_load_datetime: Some(NaiveDate::from_ymd_opt(2004, 2, 2).unwrap().and_hms_opt(14, 25, 16).unwrap()),
                 interval_events: None,
+                b2b_details: None,
             };
     
             let raw = "300,20040201,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,A,,,20040202120025,20040202142516";
@@ -722,14 +1666,68 @@ pub mod record {
             let res = record::IntervalData::parse(48, raw.into());
     
             assert_eq!(res.map(|(i,v)|(i.into_fragment(),v)),Ok(("",interval_data)));
+
+            let substituted = raw.replace(",A,,,",",F14,,,");
+            let res = record::IntervalData::parse(48, substituted.as_str().into());
+            assert_eq!(res.map(|(_,v)| v.quality_method.to_string()),Ok("F14".to_owned()));
+
+            for quality_method in ["A14","F","S1","X","F20"] {
+                let invalid = raw.replace(",A,,,",&format!(",{},,,",quality_method));
+                let res = record::IntervalData::parse(48, invalid.as_str().into());
+                assert!(matches!(res,Err(nom::Err::Failure(crate::FieldError { field: Some("quality_method"), .. }))),"{}",quality_method);
+            }
         }
     
+        #[test]
+        fn interval_values() {
+            let raw = format!("300,20040301,{}A,,,20040330095000,20041111100000", "1.5,".repeat(48));
+            for (value,expected) in [
+                ("18.023",None),
+                (".01",None),
+                ("0",None),
+                ("10000000000000",None),
+                ("1.12345",None),
+                ("-1.5",None),
+                ("123456789012.45",None),
+                ("1e5",Some("decimal number")),
+                ("inf",Some("decimal number")),
+                ("NaN",Some("decimal number")),
+                ("+1.5",Some("decimal number")),
+                ("1.",Some("decimal number")),
+                ("-",Some("decimal number")),
+                ("",Some("decimal number")),
+                ("1234567890123.45",Some("decimal number of up to 15 characters")),
+                ("1.123456",Some("decimal number with up to 5 decimal places")),
+            ] {
+                let raw = raw.replacen(",1.5,",&format!(",{},",value),1);
+                let res = record::IntervalData::parse(48, raw.as_str().into());
+                assert_eq!(res.err().map(|e| match e {
+                    nom::Err::Failure(e) => e.expected,
+                    _ => None,
+                }),expected.map(Some),"{}",value);
+            }
+
+            let nem12 = "100,NEM12,200404201300,MDA1,Ret1\n\
+            200,VABD000163,E1Q1,1,E1,N1,METSER123,kW,30,\n\
+            300,20040201,-1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,A,,,20040202120025,20040202142516\n\
+            900\n";
+            let nem12_details = crate::nem12::parse(nem12).unwrap();
+            assert_eq!(nem12_details.nmi_data_details()[0].interval_data_vec.as_ref().unwrap()[0].interval_value[0],-1.111);
+
+            let energy = nem12.replace(",kW,",",kWh,");
+            let err = crate::nem12::parse(&energy).unwrap_err();
+            assert_eq!(err.to_string(),"line 3, column 14, 300 record, field interval_value: expected value of 0 or more for an energy unit, found '-1.111'");
+            let mut reader = crate::Nem12Reader::new(energy.as_bytes()).unwrap();
+            assert_eq!(reader.next_block().unwrap().unwrap_err().to_string(),err.to_string());
+            assert_eq!(crate::nem12::parse_recovering(&energy).1[0].to_string(),err.to_string());
+        }
+
         #[test]
         fn interval_event_400() {
             let interval_event = record::IntervalEvent {
-                start_interval: "1".into(),
-                end_interval: "20".into(),
-                quality_method: "F14".into(),
+                start_interval: 1,
+                end_interval: 20,
+                quality_method: QualityMethod::new(Quality::F, Some(MethodFlag::Flag14)).unwrap(),
                 reason_code: Some("76".into()),
                 reason_description: None,
             };
@@ -739,9 +1737,9 @@ pub mod record {
             assert_eq!(res.map(|(i,v)|(i.into_fragment(),v)),Ok(("\n",interval_event)));
 
             let interval_event = record::IntervalEvent {
-                start_interval: "25".into(),
-                end_interval: "48".into(),
-                quality_method: "S14".into(),
+                start_interval: 25,
+                end_interval: 48,
+                quality_method: QualityMethod::new(Quality::S, Some(MethodFlag::Flag14)).unwrap(),
                 reason_code: Some("1".into()),
                 reason_description: None,
             };
@@ -751,9 +1749,9 @@ pub mod record {
             assert_eq!(res.map(|(i,v)|(i.into_fragment(),v)),Ok(("\n",interval_event)));
 
             let interval_event = record::IntervalEvent {
-                start_interval: "21".into(),
-                end_interval: "24".into(),
-                quality_method: "A".into(),
+                start_interval: 21,
+                end_interval: 24,
+                quality_method: QualityMethod::new(Quality::A, None).unwrap(),
                 reason_code: None,
                 reason_description: None,
             };
@@ -763,13 +1761,83 @@ pub mod record {
             assert_eq!(res.map(|(r,v)| (r.into_fragment(),v)),Ok(("\n",interval_event)));
         }
     
+        #[test]
+        fn interval_qualities() {
+            let raw = "300,20040201,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,V,,,20040202120025,20040202142516";
+            let (_,mut interval_data) = record::IntervalData::parse(48, raw.into()).unwrap();
+            let events = |raw: &[&'static str]| raw.iter().map(|r| record::IntervalEvent::parse((*r).into()).unwrap().1).collect::<Vec<_>>();
+
+            interval_data.interval_events = Some(events(&["400,1,20,F14,76,","400,21,24,A,,","400,25,48,S14,0,MeterFault"]));
+            let qualities = interval_data.interval_qualities().unwrap();
+            assert_eq!(qualities.len(),48);
+            assert_eq!(qualities[19],record::IntervalQuality {
+                quality_method: QualityMethod::new(Quality::F, Some(MethodFlag::Flag14)).unwrap(),
+                reason_code: Some("76"),
+                reason_description: None,
+            });
+            assert_eq!(qualities[20].quality_method.to_string(),"A");
+            assert_eq!(qualities[47].reason_description,Some("MeterFault"));
+
+            interval_data.interval_events = Some(events(&["400,1,20,F14,76,","400,22,48,A,,"]));
+            assert_eq!(interval_data.interval_qualities(),Err(record::IntervalEventError::Gap { interval: 21 }));
+
+            interval_data.interval_events = Some(events(&["400,1,20,F14,76,","400,20,48,A,,"]));
+            assert_eq!(interval_data.interval_qualities(),Err(record::IntervalEventError::Overlap { interval: 20 }));
+
+            interval_data.interval_events = Some(events(&["400,1,49,A,,"]));
+            assert_eq!(interval_data.interval_qualities(),Err(record::IntervalEventError::OutOfRange { start: 1, end: 49 }));
+
+            interval_data.interval_events = None;
+            assert_eq!(interval_data.interval_qualities(),Err(record::IntervalEventError::MissingEvents));
+
+            interval_data.quality_method = QualityMethod::new(Quality::A, None).unwrap();
+            assert_eq!(interval_data.interval_qualities().map(|q| q.len()),Ok(48));
+
+            interval_data.interval_events = Some(events(&["400,1,48,A,,"]));
+            assert_eq!(interval_data.interval_qualities(),Err(record::IntervalEventError::EventsWithoutVariableQuality));
+        }
+
+        #[test]
+        fn intervals() {
+            let raw = "300,20040201,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,2.222,A,,,20040202120025,20040202142516";
+            let (_,interval_data) = record::IntervalData::parse(48, raw.into()).unwrap();
+            let intervals = interval_data.intervals().unwrap().collect::<Vec<_>>();
+            assert_eq!(intervals.len(),48);
+
+            assert_eq!(intervals[0].start.to_rfc3339(),"2004-02-01T00:00:00+10:00");
+            assert_eq!(intervals[0].end.to_rfc3339(),"2004-02-01T00:30:00+10:00");
+            assert_eq!(intervals[0].end_utc().to_rfc3339(),"2004-01-31T14:30:00+00:00");
+            assert_eq!(intervals[47].start.to_rfc3339(),"2004-02-01T23:30:00+10:00");
+            assert_eq!(intervals[47].end.to_rfc3339(),"2004-02-02T00:00:00+10:00");
+            assert_eq!(intervals[47].value,2.222);
+            assert_eq!(intervals[47].quality.quality_method.to_string(),"A");
+
+            let raw = raw.replacen(",A,,,",",V,,,",1);
+            let (_,interval_data) = record::IntervalData::parse(48, raw.as_str().into()).unwrap();
+            assert!(matches!(interval_data.intervals(),Err(record::IntervalEventError::MissingEvents)));
+        }
+
+        #[cfg(feature = "tz")]
+        #[test]
+        fn intervals_in_local_time() {
+            use crate::time::Jurisdiction;
+
+            let raw = "300,20040201,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,A,,,20040202120025,20040202142516";
+            let (_,interval_data) = record::IntervalData::parse(48, raw.into()).unwrap();
+            let first = interval_data.intervals().unwrap().next().unwrap();
+
+            assert_eq!(first.end_local(Jurisdiction::Nsw).to_rfc3339(),"2004-02-01T01:30:00+11:00");
+            assert_eq!(first.end_local(Jurisdiction::Qld).to_rfc3339(),"2004-02-01T00:30:00+10:00");
+            assert_eq!(first.start_local(Jurisdiction::Sa).to_rfc3339(),"2004-02-01T00:30:00+10:30");
+        }
+    
         #[test]
         fn b2b_details_500() {
             let interval_event = record::B2BDetails {
                 trans_code: "S".into(),
-                ret_service_order: "RETNSRVCEORD1".into(),
-                read_datetime: NaiveDate::from_ymd(2003,12,20).and_hms(15,45,0),
-                index_read: "001123.5".into(),
+                ret_service_order: Some("RETNSRVCEORD1".into()),
+                read_datetime: Some(NaiveDate::from_ymd_opt(2003,12,20).unwrap().and_hms_opt(15,45,0).unwrap()),
+                index_read: Some("001123.5".into()),
             };
     
             let raw = "500,S,RETNSRVCEORD1,20031220154500,001123.5\n";
@@ -777,13 +1845,170 @@ pub mod record {
             assert_eq!(res.map(|(r,v)| (r.into_fragment(),v)),Ok(("\n",interval_event)));
         }
     
+        /// Replaces field `index` of a record, counting the record indicator as field 0.
+        fn replace_field(raw: &str, index: usize, value: &str) -> String {
+            let mut fields = raw.split(',').collect::<Vec<_>>();
+            fields[index] = value;
+            fields.join(",")
+        }
+
+        /// The field a record failed on, if it failed.
+        fn failed_field<O>(res: crate::common::IResult<crate::Input,O>) -> Option<&'static str> {
+            match res {
+                Ok(_) => None,
+                Err(nom::Err::Failure(e)) => e.field,
+                Err(_) => Some("record"),
+            }
+        }
+
+        #[test]
+        fn header_fields() {
+            let raw = "100,NEM12,200506161811,INTEGM,NEMMCO";
+            for (field,index,value,ok) in [
+                ("format",1,"NEM13",true),
+                ("format",1,"NEM14",false),
+                ("created",2,"200506161811",true),
+                ("created",2,"20050616181",false),
+                ("from_participant",3,"INTEGM",true),
+                ("from_participant",3,"CNRGYMDP",true),
+                ("from_participant",3,"MDA1",true),
+                // Example_NEM12_no_scheduled_read.csv is sent from METER_READER,
+                // longer than the 10 characters the specification allows.
+                ("from_participant",3,"METER_READER",false),
+                ("to_participant",4,"NEMMCO",true),
+                ("to_participant",4,"Ret1",true),
+                ("to_participant",4,"COMPANY",true),
+                ("to_participant",4,"",false),
+            ] {
+                let raw = replace_field(raw,index,value);
+                let res = record::Header::parse(raw.as_str().into());
+                assert_eq!(failed_field(res),(!ok).then_some(field),"{}",raw);
+            }
+        }
+
+        #[test]
+        fn nmi_data_details_fields() {
+            let raw = "200,NEM1201003,E1E2,,E1,N1,010031,kWh,15,";
+            for (field,index,value,ok) in [
+                ("nmi",1,"NEM1202026",true),
+                ("nmi",1,"NEM120202",false),
+                ("nmi",1,"NEM1-02026",false),
+                ("nmi_configuration",2,"B1E1K1Q1",true),
+                ("nmi_configuration",2,"E1 E2",false),
+                ("nmi_configuration",2,&"E1".repeat(121),false),
+                ("register_id",3,"",true),
+                ("register_id",3,"E1",true),
+                ("register_id",3,"001",true),
+                ("register_id",3,"12345678901",false),
+                ("nmi_suffix",4,"E2",true),
+                ("nmi_suffix",4,"E",false),
+                ("mdm_data_stream_id",5,"",true),
+                ("mdm_data_stream_id",5,"N",false),
+                ("meter_serial_number",6,"01004",true),
+                ("meter_serial_number",6,"METSER123",true),
+                ("meter_serial_number",6,"MTR/0001-A",true),
+                ("meter_serial_number",6,"1234567890123",false),
+                ("meter_serial_number",6,"",false),
+                ("uom",7,"KWH",true),
+                ("uom",7,"kW h",false),
+                ("next_scheduled_read_date",9,"20040420",true),
+                ("next_scheduled_read_date",9,"2004042",false),
+            ] {
+                let raw = replace_field(raw,index,value);
+                let res = record::NMIDataDetails::parse(raw.as_str().into());
+                assert_eq!(failed_field(res),(!ok).then_some(field),"{}",raw);
+            }
+        }
+
+        #[test]
+        fn interval_data_fields() {
+            let raw = format!("300,20040301,{}S15,94,ESTIMATED INTERVAL (MISSING DATA OR DATA CORRECTION),20040330095000,20041111100000", "1.5,".repeat(48));
+            for (field,index,value,ok) in [
+                ("interval_date",1,"20040229",true),
+                ("interval_date",1,"20030229",false),
+                ("reason_code",51,"",true),
+                ("reason_code",51,"1000",false),
+                ("reason_description",52,"",true),
+                ("reason_description",52,"Estimation Forecast",true),
+                ("reason_description",52,"Scenario 9 test. Nem12.mc",true),
+                ("reason_description",52,&"X".repeat(240),true),
+                ("reason_description",52,&"X".repeat(241),false),
+                ("reason_description",52,"ESTIMATED\tINTERVAL",false),
+                ("reason_description",52,"ESTIMATED\u{e9}",false),
+                ("update_datetime",53,"20040330095000",true),
+                ("update_datetime",53,"",false),
+                ("msats_load_datetime",54,"",true),
+            ] {
+                let raw = replace_field(&raw,index,value);
+                let res = record::IntervalData::parse(48, raw.as_str().into());
+                assert_eq!(failed_field(res),(!ok).then_some(field),"{}",raw);
+            }
+        }
+
+        #[test]
+        fn interval_event_fields() {
+            let raw = "400,1,10,F51,0,TESTING SCENARIO 4 REASON 1";
+            for (field,index,value,ok) in [
+                ("start_interval",1,"0001",true),
+                ("start_interval",1,"10000",false),
+                ("end_interval",2,"96",true),
+                ("end_interval",2,"",false),
+                ("reason_code",4,"",true),
+                ("reason_code",4,"A1",false),
+                ("reason_description",5,"",true),
+                ("reason_description",5,"BECAUSE I CAN",true),
+                ("reason_description",5,"MORE ESTIMATION",true),
+                ("reason_description",5,&"X".repeat(240),true),
+                ("reason_description",5,&"X".repeat(241),false),
+            ] {
+                let raw = replace_field(raw,index,value);
+                let res = record::IntervalEvent::parse(raw.as_str().into());
+                assert_eq!(failed_field(res),(!ok).then_some(field),"{}",raw);
+            }
+        }
+
+        #[test]
+        fn b2b_details_fields() {
+            let raw = "500,N,,20040304124234,026753";
+            for (field,index,value,ok) in [
+                ("trans_code",1,"E",true),
+                ("trans_code",1,"S",true),
+                ("trans_code",1,"X",false),
+                ("trans_code",1,"NS",false),
+                ("ret_service_order",2,"RETNSRVCEORD1",true),
+                ("ret_service_order",2,"SO_REQ_1",true),
+                ("ret_service_order",2,"SR0017-1",true),
+                ("ret_service_order",2,"1234567890123456",false),
+                ("read_datetime",3,"",true),
+                ("read_datetime",3,"20031220154500",true),
+                ("read_datetime",3,"200312201545",false),
+                ("index_read",4,"",true),
+                ("index_read",4,"001123.5",true),
+                ("index_read",4,"001000.0",true),
+                ("index_read",4,"1234567890123456",false),
+                ("index_read",4,"-1",false),
+            ] {
+                let raw = replace_field(raw,index,value);
+                let res = record::B2BDetails::parse(raw.as_str().into());
+                assert_eq!(failed_field(res),(!ok).then_some(field),"{}",raw);
+            }
+
+            let (_,b2b_details) = record::B2BDetails::parse("500,E,,,".into()).unwrap();
+            assert_eq!(b2b_details,record::B2BDetails {
+                trans_code: "E".into(),
+                ret_service_order: None,
+                read_datetime: None,
+                index_read: None,
+            });
+        }
+
         #[test]
         fn end_of_data_900() {
             let end_of_data = record::EndOfData {};
     
             let raw = "900\n";
             let res = record::EndOfData::parse(raw.into());
-            assert_eq!(res.map(|(r,v)| (r.into_fragment(),v)),Ok(("",end_of_data)));
+            assert_eq!(res.map(|(r,v)| (r.into_fragment(),v)),Ok(("\n",end_of_data)));
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,633 @@
+use nom::{
+    branch::permutation, bytes::complete::tag, character::complete::{alphanumeric1, char, digit1, one_of}, combinator::{cond, map_res, opt, peek, recognize, verify}, error::{make_error, ErrorKind}, sequence::{pair, preceded}, Err
+};
+
+use chrono::{NaiveDateTime,NaiveDate};
+use record::{B2BDetails, BasicMeterData, EndOfData, Header};
+use std::str;
+
+use crate::{codes::QualityMethod, common::*, error::{ParseError, StructureError, Warning, WarningKind}, options::ParseOptions};
+
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NEM13<'a> {
+    header: record::Header<'a>,
+    basic_meter_data: Vec<record::BasicMeterData<'a>>
+}
+
+/// Parses a complete NEM13 file, from the 100 header through to the 900 end of data record.
+///
+/// Lines may end with `\n` or `\r\n`, and a byte order mark before the
+/// header and blank lines between records are skipped.
+pub fn parse(input: &str) -> Result<NEM13<'_>,ParseError> {
+    NEM13::parse(input)
+}
+
+/// Parses a complete NEM13 file like [`parse`], also returning a warning for
+/// each byte order mark, blank line or change of line ending it skipped over,
+/// and for each unit of measure written in a nonstandard case.
+pub fn parse_strict(input: &str) -> Result<(NEM13<'_>,Vec<Warning>),ParseError> {
+    NEM13::parse_with(input, &ParseOptions::strict())
+}
+
+/// Parses a complete NEM13 file with the given options, returning a warning
+/// for each deviation from the specification that was accepted.
+pub fn parse_with<'a>(input: &'a str, options: &ParseOptions) -> Result<(NEM13<'a>,Vec<Warning>),ParseError> {
+    NEM13::parse_with(input, options)
+}
+
+/// A 100 header record for the NEM13 format.
+fn nem13_header(input: Input) -> IResult<Input,Header> {
+    preceded(peek(preceded(tag("100,"), field("format", "NEM13", tag("NEM13")))), Header::parse)(input)
+}
+
+/// The number of fields in a record, including its record indicator.
+fn field_count(indicator: &str) -> Option<usize> {
+    match indicator {
+        "100" | "550" => Some(5),
+        "250" => Some(23),
+        "900" => Some(1),
+        _ => None,
+    }
+}
+
+/// Parses the whole of one line with `parser`.
+fn parse_line<'a, O>(line: Input<'a>, mut parser: impl FnMut(Input<'a>) -> IResult<Input<'a>,O>) -> Result<O,ParseError> {
+    let first_line = line.location_line() as usize;
+    let (rest,o) = parser(line).map_err(|e| ParseError::from_nom(e, line, first_line))?;
+    if !rest.is_empty() {
+        return Err(ParseError::from_nom(Err::Error(make_error(rest, ErrorKind::Eof)), line, first_line));
+    }
+    Ok(o)
+}
+
+impl <'a>NEM13<'a> {
+    pub fn new(header: Header<'a>, basic_meter_data: Vec<BasicMeterData<'a>>) -> Self {
+        NEM13 {
+            header,
+            basic_meter_data
+        }
+    }
+
+    /// Parses a complete NEM13 file. See [`parse`].
+    pub fn parse(input: &'a str) -> Result<NEM13<'a>,ParseError> {
+        Self::parse_with(input, &ParseOptions::strict()).map(|(nem13,_)| nem13)
+    }
+
+    /// Parses a complete NEM13 file with the given options. See [`parse_with`].
+    ///
+    /// Records must come in the order 100, then each 250 record followed by
+    /// at most one 550 record, and finally 900.
+    pub fn parse_with(input: &'a str, options: &ParseOptions) -> Result<(NEM13<'a>,Vec<Warning>),ParseError> {
+        let lenient = options.is_lenient();
+        let mut rest: Input = strip_bom(input).into();
+        let mut header = None;
+        let mut basic_meter_data: Vec<BasicMeterData> = Vec::new();
+        let mut previous = None;
+        let mut last_line = 0;
+        let mut warnings = layout_warnings(input);
+
+        while let Some(mut line) = next_line(&mut rest) {
+            let line_no = line.location_line() as usize;
+            let indicator = line.fragment().get(..3);
+            let field_count = indicator.and_then(field_count);
+            last_line = line_no;
+
+            if let Some(stripped) = field_count.filter(|_| lenient).and_then(|count| strip_extra_commas(line, count)) {
+                line = stripped;
+                warnings.push(Warning { line: line_no, kind: WarningKind::ExtraTrailingCommas });
+            }
+
+            match (previous,indicator) {
+                (None,_) => header = Some(parse_line(line, nem13_header)?),
+                (Some("100" | "250" | "550"),Some("250")) => {
+                    let data = parse_line(line, |i| BasicMeterData::parse_with(options, i))?;
+                    if uom_in_nonstandard_case(&data.uom) {
+                        warnings.push(Warning { line: line_no, kind: WarningKind::UomCase });
+                    }
+                    // The update date and time is the 22nd field of a 250 record.
+                    if lenient && line.split(',').nth(21).is_some_and(|field| field.starts_with(' ')) {
+                        warnings.push(Warning { line: line_no, kind: WarningKind::SpaceBeforeUpdateDateTime });
+                    }
+                    basic_meter_data.push(data);
+                }
+                (Some("250"),Some("550")) => {
+                    let b2b_details = parse_line(line, |i| B2BDetails::parse_with(options, i))?;
+                    if let Some(data) = basic_meter_data.last_mut() {
+                        data.b2b_details = Some(b2b_details);
+                    }
+                }
+                (Some("250" | "550"),Some("900")) => parse_line(line, EndOfData::parse).map(|_| ())?,
+                (Some("900"),_) => return Err(ParseError::from_nom(Err::Error(make_error(line, ErrorKind::Eof)), line, line_no)),
+                _ => return Err(ParseError::from_nom(Err::Error(make_error(line, ErrorKind::Tag)), line, line_no)),
+            }
+
+            if lenient && field_count.is_some_and(|count| line.split(',').count() < count) {
+                warnings.push(Warning { line: line_no, kind: WarningKind::MissingTrailingFields });
+            }
+            previous = indicator;
+        }
+
+        let header = match (header,previous) {
+            (Some(header),Some("900")) => header,
+            (None,_) => return Err(ParseError::Structure { line: last_line + 1, error: StructureError::MissingHeader }),
+            _ => return Err(ParseError::Structure { line: last_line + 1, error: StructureError::MissingEndOfData }),
+        };
+
+        warnings.sort_by_key(|warning| warning.line);
+        Ok((NEM13::new(header, basic_meter_data),warnings))
+    }
+
+    pub fn header(&self) -> &Header<'a> {
+        &self.header
+    }
+
+    /// Copies all borrowed text out of the source, so that the result can
+    /// outlive it.
+    pub fn into_owned(self) -> NEM13<'static> {
+        NEM13 {
+            header: self.header.into_owned(),
+            basic_meter_data: self.basic_meter_data.into_iter().map(BasicMeterData::into_owned).collect(),
+        }
+    }
+
+    pub fn basic_meter_data(&self) -> &[BasicMeterData<'a>] {
+        &self.basic_meter_data
+    }
+}
+
+pub mod file {
+    #[cfg(test)]
+    use super::*;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use pretty_assertions::{assert_eq};
+
+        const NEM13_FORWARD_ESTIMATE: &str = "100,NEM13,200505231213,ETSAMDP,NEMMCO\n\
+        250,NEM1318151,1141,1,11,,18151,E,0081848.00,20050401000000,A,,,0081908.00,20050501000000,E65,77,,60,KWH,20050501,20050420113808,\n\
+        550,N,,E,\n\
+        250,NEM1318151,1141,1,11,,18151,E,0391708.00,20050501000000,S64,45,,0391908.00,20050601000000,E65,77,,200,KWH,20050601,20050520113808,\n\
+        550,S,,E,\n\
+        250,NEM1318151,1141,2,41,,18151,E,0081848.00,20050401000000,A,,,0081908.00,20050501000000,E65,77,,60,KWH,20050501,20050420113808,\n\
+        250,NEM1318151,1141,2,41,,18151,E,0391708.00,20050501000000,S64,45,,0391908.00,20050601000000,E65,77,,200,KWH,20050601,20050520113808,\n\
+        550,S,,E,\n\
+        900\n";
+
+        #[test]
+        fn nem13_from_str() {
+            let nem13_obj = NEM13::parse(NEM13_FORWARD_ESTIMATE).unwrap();
+
+            assert_eq!(nem13_obj.header().format(),"NEM13");
+            assert_eq!(nem13_obj.header().from_participant(),"ETSAMDP");
+
+            let basic_meter_data = nem13_obj.basic_meter_data();
+            assert_eq!(basic_meter_data.len(),4);
+            assert_eq!(basic_meter_data[1].previous_quality_method.to_string(),"S64");
+            assert_eq!(basic_meter_data[1].quantity,200.0);
+            assert_eq!(basic_meter_data[1].b2b_details.as_ref().map(|b| b.current_trans_code.as_ref()),Some("E"));
+            assert_eq!(basic_meter_data[2].b2b_details,None);
+            assert_eq!(basic_meter_data[3].register_id,"2");
+        }
+
+        #[test]
+        fn nem13_line_layout() {
+            use crate::error::{Warning, WarningKind};
+
+            let expected = NEM13::parse(NEM13_FORWARD_ESTIMATE).unwrap();
+            let (nem13,warnings) = crate::nem13::parse_strict(include_str!("../examples/unzipped/Example_NEM13_byte_order_mark.csv")).unwrap();
+            assert_eq!(nem13,expected);
+            assert_eq!(warnings,vec![
+                Warning { line: 1, kind: WarningKind::ByteOrderMark },
+                Warning { line: 2, kind: WarningKind::BlankLine },
+                Warning { line: 10, kind: WarningKind::BlankLine },
+                Warning { line: 12, kind: WarningKind::BlankLine },
+            ]);
+
+            assert!(NEM13::parse(&format!("{}250",NEM13_FORWARD_ESTIMATE)).is_err());
+        }
+
+        #[test]
+        fn nem13_validation_profiles() {
+            let lenient = |input| crate::nem13::parse_with(input, &ParseOptions::lenient());
+            let expected = NEM13::parse(NEM13_FORWARD_ESTIMATE).unwrap();
+
+            let input = NEM13_FORWARD_ESTIMATE.replace(",20050420113808,\n",",20050420113808\n").replacen("550,N,,E,","550,N,,E",1);
+            assert_eq!(lenient(&input).unwrap(),(expected.clone(),vec![
+                Warning { line: 2, kind: WarningKind::MissingTrailingFields },
+                Warning { line: 3, kind: WarningKind::MissingTrailingFields },
+                Warning { line: 6, kind: WarningKind::MissingTrailingFields },
+            ]));
+            assert_eq!(NEM13::parse(&input).unwrap_err().to_string(),"line 2, column 129, 250 record: expected ',', found ''");
+
+            let input = NEM13_FORWARD_ESTIMATE.replacen("NEMMCO\n","NEMMCO,\n",1).replace("550,S,,E,\n","550,S,,E,,,\n");
+            assert_eq!(lenient(&input).unwrap(),(expected.clone(),vec![
+                Warning { line: 1, kind: WarningKind::ExtraTrailingCommas },
+                Warning { line: 5, kind: WarningKind::ExtraTrailingCommas },
+                Warning { line: 8, kind: WarningKind::ExtraTrailingCommas },
+            ]));
+            assert!(NEM13::parse(&input).is_err());
+
+            let input = NEM13_FORWARD_ESTIMATE.replace(",KWH,",",kwh,");
+            let (nem13,warnings) = crate::nem13::parse_strict(&input).unwrap();
+            assert_eq!(nem13,expected);
+            assert_eq!(warnings.len(),4);
+            assert!(warnings.iter().all(|warning| warning.kind == WarningKind::UomCase));
+        }
+
+        #[test]
+        fn nem13_examples() {
+            // Both examples put a space before each update date and time,
+            // which only a lenient parse accepts.
+            let consumption = include_str!("../examples/unzipped/Example_NEM13_consumption_data.csv");
+            let forward_estimate = include_str!("../examples/unzipped/Example_NEM13_forward_estimate.csv");
+            assert_eq!(NEM13::parse(consumption).unwrap_err().to_string(),"line 2, column 115, 250 record, field update_datetime: expected date and time as YYYYMMDDhhmmss, found ' 20040108100333'");
+            assert_eq!(NEM13::parse(forward_estimate).unwrap_err().to_string(),"line 2, column 111, 250 record, field update_datetime: expected date and time as YYYYMMDDhhmmss, found ' 20040409000000'");
+
+            let (nem13,warnings) = crate::nem13::parse_with(consumption, &ParseOptions::lenient()).unwrap();
+            assert_eq!(nem13.basic_meter_data().len(),1);
+            assert_eq!(nem13.basic_meter_data()[0].update_datetime,NaiveDate::from_ymd_opt(2004,1,8).unwrap().and_hms_opt(10,3,33).unwrap());
+            assert_eq!(nem13.basic_meter_data()[0].quantity,1312.1);
+            assert_eq!(warnings,vec![Warning { line: 2, kind: WarningKind::SpaceBeforeUpdateDateTime }]);
+
+            let (nem13,warnings) = crate::nem13::parse_with(forward_estimate, &ParseOptions::lenient()).unwrap();
+            assert_eq!(nem13.basic_meter_data().len(),2);
+            assert_eq!(nem13.basic_meter_data()[1].current_quality_method.to_string(),"E64");
+            assert_eq!(nem13.basic_meter_data()[1].b2b_details.as_ref().map(|b| b.previous_trans_code.as_ref()),Some("N"));
+            assert_eq!(warnings,vec![
+                Warning { line: 2, kind: WarningKind::SpaceBeforeUpdateDateTime },
+                Warning { line: 4, kind: WarningKind::SpaceBeforeUpdateDateTime },
+            ]);
+        }
+
+        #[test]
+        fn nem13_record_order() {
+            let twice = NEM13_FORWARD_ESTIMATE.replacen("550,N,,E,\n","550,N,,E,\n550,N,,E,\n",1);
+            assert_eq!(NEM13::parse(&twice).unwrap_err().to_string(),"line 4, column 1, 550 record: expected record indicator, found '550'");
+
+            let after_end = format!("{}250",NEM13_FORWARD_ESTIMATE);
+            assert_eq!(NEM13::parse(&after_end).unwrap_err().to_string(),"line 10, column 1, 250 record: expected end of record, found '250'");
+
+            let empty = "100,NEM13,200505231213,ETSAMDP,NEMMCO\n900\n";
+            assert_eq!(NEM13::parse(empty).unwrap_err().to_string(),"line 2, column 1, 900 record: expected record indicator, found '900'");
+        }
+
+        #[test]
+        fn nem13_rejects_nem12() {
+            let nem12 = NEM13_FORWARD_ESTIMATE.replacen("NEM13","NEM12",1);
+            assert!(crate::nem13::parse(&nem12).is_err());
+
+            let missing_end = NEM13_FORWARD_ESTIMATE.replace("900\n","");
+            assert!(crate::nem13::parse(&missing_end).is_err());
+        }
+    }
+}
+
+pub mod record {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    pub use crate::nem12::record::{Header, EndOfData};
+
+    fn register_read(input: Input) -> IResult<Input,Input> {
+        section_of_max_length(
+            move |i| recognize(permutation((digit1,opt(pair(tag("."),digit1)))))(i)
+        ,15)(input)
+    }
+
+    // Basic meter data record (250)
+    #[derive(Clone,Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct BasicMeterData<'a> {
+        pub nmi: Cow<'a,str>,
+        pub nmi_configuration: Cow<'a,str>,
+        pub register_id: Cow<'a,str>,
+        pub nmi_suffix: Cow<'a,str>,
+        pub mdm_data_stream_id: Option<Cow<'a,str>>,
+        pub meter_serial_number: Cow<'a,str>,
+        pub direction_indicator: char,
+        pub previous_register_read: Cow<'a,str>,
+        pub previous_register_read_datetime: NaiveDateTime,
+        pub previous_quality_method: QualityMethod,
+        pub previous_reason_code: Option<Cow<'a,str>>,
+        pub previous_reason_description: Option<Cow<'a,str>>,
+        pub current_register_read: Cow<'a,str>,
+        pub current_register_read_datetime: NaiveDateTime,
+        pub current_quality_method: QualityMethod,
+        pub current_reason_code: Option<Cow<'a,str>>,
+        pub current_reason_description: Option<Cow<'a,str>>,
+        pub quantity: f64,
+        pub uom: Cow<'a,str>,
+        pub next_scheduled_read_date: Option<NaiveDate>,
+        pub update_datetime: NaiveDateTime,
+        pub msats_load_datetime: Option<NaiveDateTime>,
+        pub b2b_details: Option<B2BDetails<'a>>,
+    }
+
+    impl <'a>PartialEq for BasicMeterData<'a> {
+        fn eq(&self, other: &Self) -> bool {
+            self.nmi == other.nmi &&
+            self.nmi_configuration == other.nmi_configuration &&
+            self.register_id == other.register_id &&
+            self.nmi_suffix == other.nmi_suffix &&
+            self.mdm_data_stream_id == other.mdm_data_stream_id &&
+            self.meter_serial_number == other.meter_serial_number &&
+            self.direction_indicator == other.direction_indicator &&
+            self.previous_register_read == other.previous_register_read &&
+            self.previous_register_read_datetime == other.previous_register_read_datetime &&
+            self.previous_quality_method == other.previous_quality_method &&
+            self.previous_reason_code == other.previous_reason_code &&
+            self.previous_reason_description == other.previous_reason_description &&
+            self.current_register_read == other.current_register_read &&
+            self.current_register_read_datetime == other.current_register_read_datetime &&
+            self.current_quality_method == other.current_quality_method &&
+            self.current_reason_code == other.current_reason_code &&
+            self.current_reason_description == other.current_reason_description &&
+            self.quantity == other.quantity &&
+            self.uom.eq_ignore_ascii_case(&other.uom) &&
+            self.next_scheduled_read_date == other.next_scheduled_read_date &&
+            self.update_datetime == other.update_datetime &&
+            self.msats_load_datetime == other.msats_load_datetime &&
+            self.b2b_details == other.b2b_details
+        }
+    }
+
+    impl <'a>Eq for BasicMeterData<'a> { }
+
+    impl <'a>BasicMeterData<'a> {
+        pub fn parse(input: Input<'a>) -> IResult<Input<'a>,BasicMeterData<'a>> {
+            Self::parse_with(&ParseOptions::strict(), input)
+        }
+
+        /// Parses a 250 record, allowing the deviations of a lenient profile.
+        pub fn parse_with(options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,BasicMeterData<'a>> {
+            basic_meter_data(options, input)
+        }
+
+        pub fn into_owned(self) -> BasicMeterData<'static> {
+            BasicMeterData {
+                nmi: owned(self.nmi),
+                nmi_configuration: owned(self.nmi_configuration),
+                register_id: owned(self.register_id),
+                nmi_suffix: owned(self.nmi_suffix),
+                mdm_data_stream_id: self.mdm_data_stream_id.map(owned),
+                meter_serial_number: owned(self.meter_serial_number),
+                direction_indicator: self.direction_indicator,
+                previous_register_read: owned(self.previous_register_read),
+                previous_register_read_datetime: self.previous_register_read_datetime,
+                previous_quality_method: self.previous_quality_method,
+                previous_reason_code: self.previous_reason_code.map(owned),
+                previous_reason_description: self.previous_reason_description.map(owned),
+                current_register_read: owned(self.current_register_read),
+                current_register_read_datetime: self.current_register_read_datetime,
+                current_quality_method: self.current_quality_method,
+                current_reason_code: self.current_reason_code.map(owned),
+                current_reason_description: self.current_reason_description.map(owned),
+                quantity: self.quantity,
+                uom: owned(self.uom),
+                next_scheduled_read_date: self.next_scheduled_read_date,
+                update_datetime: self.update_datetime,
+                msats_load_datetime: self.msats_load_datetime,
+                b2b_details: self.b2b_details.map(B2BDetails::into_owned),
+            }
+        }
+    }
+
+    fn basic_meter_data<'a>(options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,BasicMeterData<'a>> {
+        let (input, _) = tag("250,")(input)?;
+        let (input, nmi) = field("nmi", "10 alphanumeric characters", section_of_exact_length(alphanumeric1, 10))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, nmi_configuration) = field("nmi_configuration", "up to 240 alphanumeric characters", section_of_max_length(alphanumeric1, 240))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, register_id) = field("register_id", "up to 10 alphanumeric characters", section_of_max_length(alphanumeric1, 10))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, nmi_suffix) = field("nmi_suffix", "2 alphanumeric characters", section_of_exact_length(alphanumeric1, 2))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, mdm_data_stream_id) = field("mdm_data_stream_id", "2 alphanumeric characters or nothing", optional_field(section_of_exact_length(alphanumeric1, 2),","))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, meter_serial_number) = field("meter_serial_number", "up to 12 alphanumeric characters", section_of_max_length(alphanumeric1, 12))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, direction_indicator) = field("direction_indicator", "E or I", one_of("EI"))(input)?;
+        let (input, _) = tag(",")(input)?;
+
+        let (input, previous_register_read) = field("previous_register_read", "register read of up to 15 characters", register_read)(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, previous_register_read_datetime) = field("previous_register_read_datetime", "date and time as YYYYMMDDhhmmss", datetime_14)(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, previous_quality_method) = field("previous_quality_method", "quality flag, with a method flag for E, F and S", quality_method)(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, previous_reason_code) = field("previous_reason_code", "up to 3 digits or nothing", optional_field(section_of_max_length(digit1, 3),","))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, previous_reason_description) = field("previous_reason_description", "up to 240 characters or nothing", optional_field(section_of_max_length(varchar, 240),","))(input)?;
+        let (input, _) = tag(",")(input)?;
+
+        let (input, current_register_read) = field("current_register_read", "register read of up to 15 characters", register_read)(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, current_register_read_datetime) = field("current_register_read_datetime", "date and time as YYYYMMDDhhmmss", datetime_14)(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, current_quality_method) = field("current_quality_method", "quality flag, with a method flag for E, F and S", quality_method)(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, current_reason_code) = field("current_reason_code", "up to 3 digits or nothing", optional_field(section_of_max_length(digit1, 3),","))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, current_reason_description) = field("current_reason_description", "up to 240 characters or nothing", optional_field(section_of_max_length(varchar, 240),","))(input)?;
+        let (input, _) = tag(",")(input)?;
+
+        let quantity = map_res(verify(decimal, |n: &Input| n.len() <= MAX_NUMERIC_LENGTH), |n: Input| n.parse::<f64>());
+        let (input, quantity) = field("quantity", "decimal number of up to 15 characters", quantity)(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, uom) = field("uom", "up to 5 alphanumeric characters", section_of_max_length(alphanumeric1, 5))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, next_scheduled_read_date) = field("next_scheduled_read_date", "date as YYYYMMDD or nothing", optional_field(date_8,","))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let space = cond(options.is_lenient(), opt(char(' ')));
+        let (input, update_datetime) = field("update_datetime", "date and time as YYYYMMDDhhmmss", preceded(space, datetime_14))(input)?;
+        let (input, msats_load_datetime) = trailing_field(options.is_lenient(), opt(datetime_14))(input)?;
+
+        let basic_meter_data = BasicMeterData {
+            nmi: text(nmi),
+            nmi_configuration: text(nmi_configuration),
+            register_id: text(register_id),
+            nmi_suffix: text(nmi_suffix),
+            mdm_data_stream_id: mdm_data_stream_id.map(text),
+            meter_serial_number: text(meter_serial_number),
+            direction_indicator,
+            previous_register_read: text(previous_register_read),
+            previous_register_read_datetime,
+            previous_quality_method,
+            previous_reason_code: previous_reason_code.map(text),
+            previous_reason_description: previous_reason_description.map(text),
+            current_register_read: text(current_register_read),
+            current_register_read_datetime,
+            current_quality_method,
+            current_reason_code: current_reason_code.map(text),
+            current_reason_description: current_reason_description.map(text),
+            quantity,
+            uom: text(uom),
+            next_scheduled_read_date,
+            update_datetime,
+            msats_load_datetime,
+            b2b_details: None,
+        };
+
+        Ok((input,basic_meter_data))
+    }
+
+    // B2B details record (550)
+    #[derive(Clone,Debug,PartialEq,Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct B2BDetails<'a> {
+        pub previous_trans_code: Cow<'a,str>,
+        pub previous_ret_service_order: Option<Cow<'a,str>>,
+        pub current_trans_code: Cow<'a,str>,
+        pub current_ret_service_order: Option<Cow<'a,str>>,
+    }
+
+    impl <'a>B2BDetails<'a> {
+        pub fn parse(input: Input<'a>) -> IResult<Input<'a>,B2BDetails<'a>> {
+            Self::parse_with(&ParseOptions::strict(), input)
+        }
+
+        /// Parses a 550 record, allowing the deviations of a lenient profile.
+        pub fn parse_with(options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,B2BDetails<'a>> {
+            b2b_details(options, input)
+        }
+
+        pub fn into_owned(self) -> B2BDetails<'static> {
+            B2BDetails {
+                previous_trans_code: owned(self.previous_trans_code),
+                previous_ret_service_order: self.previous_ret_service_order.map(owned),
+                current_trans_code: owned(self.current_trans_code),
+                current_ret_service_order: self.current_ret_service_order.map(owned),
+            }
+        }
+    }
+
+    fn b2b_details<'a>(options: &ParseOptions, input: Input<'a>) -> IResult<Input<'a>,B2BDetails<'a>> {
+        let (input, _) = tag("550,")(input)?;
+        let (input, previous_trans_code) = field("previous_trans_code", "transaction code A, C, G, D, E, N, O, S or R", transaction_code)(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, previous_ret_service_order) = field("previous_ret_service_order", "up to 15 characters or nothing", optional_field(section_of_max_length(varchar,15),","))(input)?;
+        let (input, _) = tag(",")(input)?;
+        let (input, current_trans_code) = field("current_trans_code", "transaction code A, C, G, D, E, N, O, S or R", transaction_code)(input)?;
+        let (input, current_ret_service_order) = trailing_field(options.is_lenient(), field("current_ret_service_order", "up to 15 characters or nothing", opt(section_of_max_length(varchar,15))))(input)?;
+
+        let b2b_details = B2BDetails {
+            previous_trans_code: text(previous_trans_code),
+            previous_ret_service_order: previous_ret_service_order.map(text),
+            current_trans_code: text(current_trans_code),
+            current_ret_service_order: current_ret_service_order.map(text)
+        };
+
+        Ok((input,b2b_details))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::record;
+        use crate::codes::{Quality, QualityMethod};
+        use chrono::NaiveDate;
+
+        #[test]
+        fn basic_meter_data_250() {
+            let basic_meter_data = record::BasicMeterData {
+                nmi: "VABC005890".into(),
+                nmi_configuration: "11".into(),
+                register_id: "1".into(),
+                nmi_suffix: "11".into(),
+                mdm_data_stream_id: Some("11".into()),
+                meter_serial_number: "METSER123".into(),
+                direction_indicator: 'E',
+                previous_register_read: "006342.8".into(),
+                previous_register_read_datetime: NaiveDate::from_ymd_opt(2003,10,5).unwrap().and_hms_opt(9,30,55).unwrap(),
+                previous_quality_method: QualityMethod::new(Quality::A, None).unwrap(),
+                previous_reason_code: None,
+                previous_reason_description: None,
+                current_register_read: "007654.9".into(),
+                current_register_read_datetime: NaiveDate::from_ymd_opt(2004,1,7).unwrap().and_hms_opt(10,3,33).unwrap(),
+                current_quality_method: QualityMethod::new(Quality::A, None).unwrap(),
+                current_reason_code: None,
+                current_reason_description: None,
+                quantity: 1312.1,
+                uom: "kWh".into(),
+                next_scheduled_read_date: Some(NaiveDate::from_ymd_opt(2004,4,7).unwrap()),
+                update_datetime: NaiveDate::from_ymd_opt(2004,1,8).unwrap().and_hms_opt(10,3,33).unwrap(),
+                msats_load_datetime: Some(NaiveDate::from_ymd_opt(2004,1,8).unwrap().and_hms_opt(9,11,33).unwrap()),
+                b2b_details: None,
+            };
+
+            let raw = "250,VABC005890,11,1,11,11,METSER123,E,006342.8,20031005093055,A,,,007654.9,20040107100333,A,,,1312.1,kWh,20040407,20040108100333,20040108091133\n";
+            let res = record::BasicMeterData::parse(raw.into());
+            assert_eq!(res.map(|(i,v)|(i.into_fragment(),v)),Ok(("\n",basic_meter_data)));
+
+            let raw = "250,NEM1312028,12,1,12,12,12028,I,00105.4,20040119124311,A,,,00110.5,20040211111445,E62,77,,-5.100,kWh,20040513,20040211211920,\n";
+            let (input,basic_meter_data) = record::BasicMeterData::parse(raw.into()).unwrap();
+            assert_eq!(input.into_fragment(),"\n");
+            assert_eq!(basic_meter_data.direction_indicator,'I');
+            assert_eq!(basic_meter_data.quantity,-5.1);
+            assert_eq!(basic_meter_data.current_quality_method.to_string(),"E62");
+            assert_eq!(basic_meter_data.current_reason_code.as_deref(),Some("77"));
+            assert_eq!(basic_meter_data.msats_load_datetime,None);
+
+            let raw = "250,NEM1312028,12,1,12,12,12028,I,00105.4,20040119124311,F52,74,Long Interval Alarm,00110.5,20040211111445,E62,77,Locked Gate,-5.100,kWh,20040513,20040211211920,\n";
+            let (_,basic_meter_data) = record::BasicMeterData::parse(raw.into()).unwrap();
+            assert_eq!(basic_meter_data.previous_reason_description.as_deref(),Some("Long Interval Alarm"));
+            assert_eq!(basic_meter_data.current_reason_description.as_deref(),Some("Locked Gate"));
+
+            for quantity in ["1e3","inf","NaN","+5.1","1234567890123456"] {
+                let raw = format!("250,NEM1312028,12,1,12,12,12028,I,00105.4,20040119124311,A,,,00110.5,20040211111445,A,,,{},kWh,20040513,20040211211920,\n",quantity);
+                assert!(record::BasicMeterData::parse(raw.as_str().into()).is_err(),"{}",quantity);
+            }
+
+            let raw = "250,NEM1312028,12,1,12,12,12028,X,00105.4,20040119124311,A,,,00110.5,20040211111445,A,,,-5.100,kWh,20040513,20040211211920,\n";
+            assert!(record::BasicMeterData::parse(raw.into()).is_err());
+        }
+
+        #[test]
+        fn b2b_details_550() {
+            let b2b_details = record::B2BDetails {
+                previous_trans_code: "S".into(),
+                previous_ret_service_order: Some("12345".into()),
+                current_trans_code: "S".into(),
+                current_ret_service_order: Some("23456".into()),
+            };
+
+            let raw = "550,S,12345,S,23456\n";
+            let res = record::B2BDetails::parse(raw.into());
+            assert_eq!(res.map(|(r,v)| (r.into_fragment(),v)),Ok(("\n",b2b_details)));
+
+            let b2b_details = record::B2BDetails {
+                previous_trans_code: "N".into(),
+                previous_ret_service_order: None,
+                current_trans_code: "E".into(),
+                current_ret_service_order: None,
+            };
+
+            let raw = "550,N,,E,\n";
+            let res = record::B2BDetails::parse(raw.into());
+            assert_eq!(res.map(|(r,v)| (r.into_fragment(),v)),Ok(("\n",b2b_details)));
+
+            for (raw,previous,current) in [
+                ("550,N,SR0017-1,S,SO_REQ_1\n",Some("SR0017-1"),Some("SO_REQ_1")),
+                ("550,S,,S,OTHR0016-1\n",None,Some("OTHR0016-1")),
+            ] {
+                let (input,b2b_details) = record::B2BDetails::parse(raw.into()).unwrap();
+                assert_eq!(input.into_fragment(),"\n");
+                assert_eq!(b2b_details.previous_ret_service_order.as_deref(),previous);
+                assert_eq!(b2b_details.current_ret_service_order.as_deref(),current);
+            }
+
+            let raw = "100,NEM13,200402070911,MDA1,Ret1\n\
+            250,VABC005890,11,1,11,11,METSER123,E,006342.8,20031005093055,A,,,007654.9,20040107100333,A,,,1312.1,kWh,20040407,20040108100333,20040108091133\n\
+            550,S,SERVICEORDER1234,S,\n\
+            900\n";
+            let error = crate::nem13::parse(raw).unwrap_err();
+            assert!(error.to_string().contains("field previous_ret_service_order: expected up to 15 characters or nothing"),"{}",error);
+
+            for raw in ["550,X,,E,\n","550,N,,1,\n","550,NS,,E,\n"] {
+                assert!(record::B2BDetails::parse(raw.into()).is_err(),"{}",raw);
+            }
+        }
+    }
+}
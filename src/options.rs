@@ -0,0 +1,36 @@
+/// How closely a file must follow the MDFF specification.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Hash)]
+pub enum ValidationProfile {
+    /// Reject anything the specification doesn't allow.
+    #[default]
+    Strict,
+    /// Accept the deviations some MDPs are known to send, reporting each as a
+    /// [`crate::Warning`]:
+    ///
+    /// - optional fields missing altogether from the end of a 200, 250, 300,
+    ///   400 or 550 record, including the update date and time of a 300 record
+    /// - commas after the last field of a record
+    /// - a space before the update date and time of a 250 record
+    Lenient,
+}
+
+/// Options for [`crate::parse_with`], [`crate::nem12::parse_with`],
+/// [`crate::nem13::parse_with`] and [`crate::Nem12Reader::with_options`].
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Hash)]
+pub struct ParseOptions {
+    pub profile: ValidationProfile,
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        ParseOptions { profile: ValidationProfile::Strict }
+    }
+
+    pub fn lenient() -> Self {
+        ParseOptions { profile: ValidationProfile::Lenient }
+    }
+
+    pub(crate) fn is_lenient(&self) -> bool {
+        self.profile == ValidationProfile::Lenient
+    }
+}
@@ -0,0 +1,302 @@
+use chrono::NaiveDateTime;
+
+use std::io::BufRead;
+
+use crate::{
+    common::Layout,
+    error::{ParseError, StructureError, Warning},
+    nem12::{parse_block, records_with, record::{Header, Kind, NMIDataDetails}, Record, State},
+    options::ParseOptions,
+};
+
+/// Reads a NEM12 file from any `BufRead` one NMI data details block (a 200
+/// record and its 300, 400 and 500 children) at a time.
+///
+/// Only the current block is held in memory, so the returned block borrows
+/// from the reader and must be dropped before reading the next one.
+///
+/// Records are read as [`crate::nem12::parse_with`] reads them, so lines may
+/// end with `\n` or `\r\n`, a byte order mark and blank lines are skipped,
+/// and each deviation accepted is noted in [`Nem12Reader::warnings`].
+pub struct Nem12Reader<R> {
+    reader: R,
+    options: ParseOptions,
+    format: String,
+    created: NaiveDateTime,
+    from_participant: String,
+    to_participant: String,
+    line: String,
+    line_no: usize,
+    blank_lines: usize,
+    pending: bool,
+    block: String,
+    state: State,
+    layout: Layout,
+    finished: bool,
+}
+
+impl <R: BufRead>Nem12Reader<R> {
+    /// Reads the 100 header record and returns a reader positioned at the first block.
+    pub fn new(reader: R) -> Result<Self,ParseError> {
+        Self::with_options(reader, ParseOptions::strict())
+    }
+
+    /// Reads the 100 header record like [`Nem12Reader::new`], reading every
+    /// record with the given options.
+    pub fn with_options(mut reader: R, options: ParseOptions) -> Result<Self,ParseError> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut layout = Layout::default();
+        layout.line(1, &line);
+
+        let mut records = records_with(&line, options);
+        let header = match records.next() {
+            Some(Ok(Record { kind: Kind::Header(header), .. })) => header,
+            Some(Err(e)) => return Err(e),
+            Some(Ok(_)) | None => return Err(ParseError::Structure { line: 1, error: StructureError::MissingHeader }),
+        };
+        layout.warnings.extend_from_slice(records.warnings());
+
+        Ok(Nem12Reader {
+            format: header.format().to_owned(),
+            created: header.created(),
+            from_participant: header.from_participant().to_owned(),
+            to_participant: header.to_participant().to_owned(),
+            reader,
+            options,
+            line: String::new(),
+            line_no: 1,
+            blank_lines: 0,
+            pending: false,
+            block: String::new(),
+            state: State::Header,
+            layout,
+            finished: false,
+        })
+    }
+
+    pub fn header(&self) -> Header<'_> {
+        Header::new(
+            self.format.as_str().into(),
+            self.created,
+            self.from_participant.as_str().into(),
+            self.to_participant.as_str().into(),
+        )
+    }
+
+    /// The warnings for the lines read so far, in line order. See [`crate::nem12::parse_with`].
+    pub fn warnings(&self) -> &[Warning] {
+        &self.layout.warnings
+    }
+
+    /// Reads and parses the next NMI data details block, returning `None` once
+    /// the 900 end of data record has been read.
+    pub fn next_block(&mut self) -> Option<Result<NMIDataDetails<'_>,ParseError>> {
+        if self.finished {
+            return None;
+        }
+
+        let first_line = match self.read_block() {
+            Ok(first_line) => first_line,
+            Err(e) => {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        };
+
+        let (res,warnings) = parse_block(&self.block, first_line, self.options);
+        self.layout.warnings.extend(warnings);
+        self.layout.warnings.sort_by_key(|warning| warning.line);
+        if res.is_err() {
+            self.finished = true;
+        }
+        res.transpose()
+    }
+
+    /// Fills `block` with the lines of the next block, or with the 900 record
+    /// once it is reached, returning the line number of its first line.
+    fn read_block(&mut self) -> Result<usize,ParseError> {
+        if !self.pending && !self.read_line()? {
+            return Err(ParseError::Structure { line: self.line_no + 1, error: StructureError::MissingEndOfData });
+        }
+        self.pending = false;
+
+        let first_line = self.line_no;
+        self.block.clear();
+        self.block.push_str(&self.line);
+
+        match self.next_state()? {
+            State::EndOfData => {
+                self.finished = true;
+                return Ok(first_line);
+            }
+            State::NMIDataDetails => {}
+            _ => return Err(ParseError::Structure { line: self.line_no, error: StructureError::ExpectedDetails }),
+        }
+
+        while self.read_line()? {
+            if self.line.starts_with("200") || self.line.starts_with("900") {
+                self.pending = true;
+                break;
+            }
+            self.next_state()?;
+            // Keep skipped blank lines so that records are numbered by their line in the file.
+            for _ in 0..self.blank_lines {
+                self.block.push('\n');
+            }
+            self.block.push_str(&self.line);
+        }
+
+        Ok(first_line)
+    }
+
+    /// Checks that the record in `line` may follow the records before it.
+    fn next_state(&mut self) -> Result<State,ParseError> {
+        let indicator = self.line.get(..3).unwrap_or("");
+        self.state = self.state.next(indicator)
+            .map_err(|error| ParseError::Structure { line: self.line_no, error })?;
+        Ok(self.state)
+    }
+
+    /// Reads the next line that isn't blank into `line`, always terminated by
+    /// a line ending, counting the blank lines skipped before it.
+    fn read_line(&mut self) -> Result<bool,ParseError> {
+        self.blank_lines = 0;
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(false);
+            }
+            self.line_no += 1;
+            self.layout.line(self.line_no, &self.line);
+
+            if !self.line.ends_with('\n') {
+                self.line.push('\n');
+            }
+            if self.line.trim_end_matches(['\r', '\n']).is_empty() {
+                self.blank_lines += 1;
+                continue;
+            }
+
+            return Ok(true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPLE_BLOCKS: &str = "100,NEM12,200404201300,MDA1,Ret1\n\
+    200,CCCC123456,E1,001,E1,N1,METSER123,kWh,30,\n\
+    300,20040417,18.023,19.150,17.592,24.155,18.568,22.304,19.222,19.032,19.090,22.237,24.350,22.274,20.193,16.615,19.575,20.391,16.459,20.527,21.438,19.327,21.424,16.656,17.616,18.416,16.666,19.961,18.120,18.023,18.588,21.759,17.841,19.548,18.486,21.391,15.656,16.634,16.377,14.246,17.451,15.742,18.038,18.470,14.936,17.987,15.751,19.750,16.202,14.733,V,,,20040418203500,20040419003500\n\
+    400,1,20,F14,76,\n\
+    400,21,24,A,,\n\
+    400,25,48,S14,1,\n\
+    200,VABD000163,E1Q1,1,E1,N1,METSER123,kWh,30,\n\
+    300,20040201,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,A,,,20040202120025,20040202142516\n\
+    300,20040202,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,A,,,20040203120025,20040203142516\n\
+    500,S,RETNSRVCEORD1,20031220154500,001123.5\n\
+    900\n";
+
+    #[test]
+    fn read_blocks() {
+        let nem12 = crate::nem12::parse(MULTIPLE_BLOCKS).unwrap();
+        let mut reader = Nem12Reader::new(MULTIPLE_BLOCKS.as_bytes()).unwrap();
+        assert_eq!(&reader.header(),nem12.header());
+
+        let mut i = 0;
+        while let Some(block) = reader.next_block() {
+            assert_eq!(block.unwrap(),nem12.nmi_data_details()[i]);
+            i += 1;
+        }
+        assert_eq!(i,2);
+        assert!(reader.next_block().is_none());
+    }
+
+    #[test]
+    fn read_without_trailing_newline() {
+        let input = MULTIPLE_BLOCKS.trim_end();
+        let mut reader = Nem12Reader::new(input.as_bytes()).unwrap();
+        assert!(reader.next_block().unwrap().is_ok());
+        assert!(reader.next_block().unwrap().is_ok());
+        assert!(reader.next_block().is_none());
+    }
+
+    #[test]
+    fn read_line_layouts() {
+        for input in [
+            include_str!("../examples/unzipped/Example_NEM12_byte_order_mark.csv"),
+            include_str!("../examples/unzipped/Example_NEM12_blank_lines.csv"),
+            include_str!("../examples/unzipped/Example_NEM12_mixed_line_endings.csv"),
+        ] {
+            let (nem12,mut warnings) = crate::nem12::parse_strict(input).unwrap();
+            // The reader stops at the 900 record, before any blank lines after it.
+            let end_of_data = input.lines().position(|line| line.starts_with("900")).unwrap() + 1;
+            warnings.retain(|warning| warning.line <= end_of_data);
+            let mut reader = Nem12Reader::new(input.as_bytes()).unwrap();
+            assert_eq!(&reader.header(),nem12.header());
+
+            let mut i = 0;
+            while let Some(block) = reader.next_block() {
+                assert_eq!(block.unwrap(),nem12.nmi_data_details()[i]);
+                i += 1;
+            }
+            assert_eq!(i,nem12.nmi_data_details().len());
+            assert_eq!(reader.warnings(),&warnings[..]);
+        }
+    }
+
+    /// Reads every block of `input`, in the shape [`crate::nem12::parse_with`] returns.
+    fn read_all(input: &str, options: ParseOptions) -> Result<(Vec<NMIDataDetails<'static>>,Vec<Warning>),String> {
+        let mut reader = Nem12Reader::with_options(input.as_bytes(), options).map_err(|e| e.to_string())?;
+        let mut blocks = Vec::new();
+        while let Some(block) = reader.next_block() {
+            blocks.push(block.map_err(|e| e.to_string())?.into_owned());
+        }
+        Ok((blocks,reader.warnings().to_vec()))
+    }
+
+    #[test]
+    fn read_like_parse_with() {
+        for input in [
+            MULTIPLE_BLOCKS.to_owned(),
+            MULTIPLE_BLOCKS.replace(",kWh,",",kwh,"),
+            MULTIPLE_BLOCKS.replacen("20040417,18.023,","20040417,-18.023,",1),
+            MULTIPLE_BLOCKS.replace("400,21,24,A,,","400,21,24,A"),
+            MULTIPLE_BLOCKS.replace("001123.5\n","001123.5,,\n"),
+            MULTIPLE_BLOCKS.replace("\n300,20040202","\n\n300,20040202").replace('\n',"\r\n"),
+            MULTIPLE_BLOCKS.replace("20040202,1.111,","20040202,x.111,"),
+        ] {
+            for options in [ParseOptions::strict(),ParseOptions::lenient()] {
+                let parsed = crate::nem12::parse_with(&input, &options)
+                    .map(|(nem12,warnings)| (nem12.into_owned().nmi_data_details().to_vec(),warnings))
+                    .map_err(|e| e.to_string());
+                assert_eq!(read_all(&input, options),parsed,"{:?} {}",options,input);
+            }
+        }
+    }
+
+    #[test]
+    fn read_errors() {
+        let input = MULTIPLE_BLOCKS.replace("20040202,1.111,","20040202,x.111,");
+        let mut reader = Nem12Reader::new(input.as_bytes()).unwrap();
+        assert!(reader.next_block().unwrap().is_ok());
+        assert_eq!(reader.next_block().unwrap().unwrap_err().line(),Some(9));
+        assert!(reader.next_block().is_none());
+
+        let input = MULTIPLE_BLOCKS.replace("900\n","");
+        let mut reader = Nem12Reader::new(input.as_bytes()).unwrap();
+        assert!(reader.next_block().unwrap().is_ok());
+        assert!(reader.next_block().unwrap().is_ok());
+        assert!(matches!(reader.next_block(),Some(Err(ParseError::Structure { line: 11, .. }))));
+
+        let input = MULTIPLE_BLOCKS.replace("200,VABD000163","300,VABD000163");
+        let mut reader = Nem12Reader::new(input.as_bytes()).unwrap();
+        assert_eq!(reader.next_block().unwrap().unwrap_err().line(),Some(7));
+        assert!(reader.next_block().is_none());
+
+        let input = MULTIPLE_BLOCKS.replacen("NEM12","NEM13",1);
+        assert!(Nem12Reader::new(input.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+
+/// NEM market time, Australian Eastern Standard Time (UTC+10) all year round.
+/// Interval data is always recorded in market time, whatever the local time
+/// at the meter.
+pub const MARKET_TIME: FixedOffset = match FixedOffset::east_opt(10 * 3600) {
+    Some(offset) => offset,
+    None => panic!("invalid market time offset"),
+};
+
+/// Attaches the market time offset to a date and time read from a file.
+pub fn market_datetime(datetime: NaiveDateTime) -> DateTime<FixedOffset> {
+    DateTime::from_naive_utc_and_offset(datetime - MARKET_TIME, MARKET_TIME)
+}
+
+/// A NEM jurisdiction, for converting market time to local time with daylight saving.
+#[cfg(feature = "tz")]
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum Jurisdiction {
+    Act,
+    Nsw,
+    Qld,
+    Sa,
+    Tas,
+    Vic,
+}
+
+#[cfg(feature = "tz")]
+impl Jurisdiction {
+    pub fn tz(&self) -> chrono_tz::Tz {
+        match self {
+            Jurisdiction::Act => chrono_tz::Australia::Canberra,
+            Jurisdiction::Nsw => chrono_tz::Australia::Sydney,
+            Jurisdiction::Qld => chrono_tz::Australia::Brisbane,
+            Jurisdiction::Sa => chrono_tz::Australia::Adelaide,
+            Jurisdiction::Tas => chrono_tz::Australia::Hobart,
+            Jurisdiction::Vic => chrono_tz::Australia::Melbourne,
+        }
+    }
+}
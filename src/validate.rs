@@ -0,0 +1,237 @@
+use chrono::NaiveDate;
+
+use std::fmt;
+
+use crate::{
+    codes::{MethodFlag, Quality, QualityMethod},
+    common::Format,
+    nem12::{NEM12, record::IntervalEventError},
+    nem13::NEM13,
+};
+
+/// The metering installation types whose data each file format carries.
+/// NEM12 holds interval data (types 1 to 5 and unmetered type 7) and NEM13
+/// holds accumulation data (type 6).
+fn installation_types(format: Format) -> &'static [u8] {
+    match format {
+        Format::Nem12 => &[1, 2, 3, 4, 5, 7],
+        Format::Nem13 => &[6],
+    }
+}
+
+/// A problem with the meaning of otherwise well formed records.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum ValidationError {
+    /// A method flag that isn't an estimation method for E data, or a
+    /// substitution method for F and S data, e.g. `E53`.
+    MethodForQuality { nmi: String, date: NaiveDate, quality_method: QualityMethod },
+    /// A method flag for an installation type that the file format does not carry.
+    InstallationType { nmi: String, date: NaiveDate, quality_method: QualityMethod, format: Format },
+    /// An NMI whose records use methods from more than one installation type,
+    /// with the first method seen from each.
+    MixedInstallationTypes { nmi: String, methods: Vec<MethodFlag> },
+    /// A day whose 400 records don't match its quality or don't cover its intervals.
+    IntervalEvents { nmi: String, date: NaiveDate, error: IntervalEventError },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MethodForQuality { nmi, date, quality_method } => {
+                let kind = if quality_method.quality() == Quality::E { "an estimation" } else { "a substitution" };
+                write!(f, "NMI {} on {}: method {} is not {} method", nmi, date, quality_method, kind)
+            }
+            ValidationError::InstallationType { nmi, date, quality_method, format } => {
+                write!(f, "NMI {} on {}: method {} is not used for {} installation types", nmi, date, quality_method, format)
+            }
+            ValidationError::MixedInstallationTypes { nmi, methods } => {
+                write!(f, "NMI {} mixes methods from several installation types:", nmi)?;
+                for method in methods {
+                    write!(f, " {}", method)?;
+                }
+                Ok(())
+            }
+            ValidationError::IntervalEvents { nmi, date, error } => write!(f, "NMI {} on {}: {}", nmi, date, error),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError { }
+
+/// Checks the method flags of every NMI against their quality flags and the
+/// installation types of the file format, one NMI at a time in order of first
+/// appearance.
+struct MethodCheck {
+    format: Format,
+    nmis: Vec<(String,Vec<MethodFlag>)>,
+    errors: Vec<ValidationError>,
+}
+
+impl MethodCheck {
+    fn new(format: Format) -> Self {
+        MethodCheck { format, nmis: Vec::new(), errors: Vec::new() }
+    }
+
+    fn check(&mut self, nmi: &str, date: NaiveDate, quality_method: QualityMethod) {
+        let method = match quality_method.method() {
+            Some(method) => method,
+            None => return,
+        };
+
+        if !quality_method.method_fits() {
+            self.errors.push(ValidationError::MethodForQuality {
+                nmi: nmi.to_owned(),
+                date,
+                quality_method,
+            });
+        }
+        if !installation_types(self.format).iter().any(|t| method.meta().applies_to(*t)) {
+            self.errors.push(ValidationError::InstallationType {
+                nmi: nmi.to_owned(),
+                date,
+                quality_method,
+                format: self.format,
+            });
+        }
+
+        let i = match self.nmis.iter().position(|(n,_)| n == nmi) {
+            Some(i) => i,
+            None => {
+                self.nmis.push((nmi.to_owned(),Vec::new()));
+                self.nmis.len() - 1
+            }
+        };
+        let methods = &mut self.nmis[i].1;
+        if !methods.iter().any(|m| same_installation_types(*m, method)) {
+            methods.push(method);
+        }
+    }
+
+    fn finish(mut self) -> Vec<ValidationError> {
+        for (nmi,methods) in self.nmis {
+            if methods.len() > 1 {
+                self.errors.push(ValidationError::MixedInstallationTypes { nmi, methods });
+            }
+        }
+        self.errors
+    }
+}
+
+/// Method flags are numbered by installation type: 1x for types 1 to 4, then
+/// 5x, 6x and 7x for types 5, 6 and 7.
+fn same_installation_types(a: MethodFlag, b: MethodFlag) -> bool {
+    a.code() / 10 == b.code() / 10
+}
+
+/// Validates the method flags and interval events of the 300 and 400 records of a NEM12 file.
+pub fn nem12(nem12: &NEM12) -> Vec<ValidationError> {
+    let mut check = MethodCheck::new(Format::Nem12);
+
+    for details in nem12.nmi_data_details() {
+        for interval_data in details.interval_data_vec.iter().flatten() {
+            if let Err(error) = interval_data.interval_qualities() {
+                check.errors.push(ValidationError::IntervalEvents {
+                    nmi: details.nmi.to_string(),
+                    date: interval_data.interval_date,
+                    error,
+                });
+            }
+            check.check(&details.nmi, interval_data.interval_date, interval_data.quality_method);
+            for interval_event in interval_data.interval_events.iter().flatten() {
+                check.check(&details.nmi, interval_data.interval_date, interval_event.quality_method);
+            }
+        }
+    }
+
+    check.finish()
+}
+
+/// Validates the method flags of the 250 records of a NEM13 file.
+pub fn nem13(nem13: &NEM13) -> Vec<ValidationError> {
+    let mut check = MethodCheck::new(Format::Nem13);
+
+    for data in nem13.basic_meter_data() {
+        check.check(&data.nmi, data.previous_register_read_datetime.date(), data.previous_quality_method);
+        check.check(&data.nmi, data.current_register_read_datetime.date(), data.current_quality_method);
+    }
+
+    check.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEM12_SUBSTITUTED: &str = "100,NEM12,200404201300,MDA1,Ret1\n\
+    200,CCCC123456,E1,001,E1,N1,METSER123,kWh,30,\n\
+    300,20040417,18.023,19.150,17.592,24.155,18.568,22.304,19.222,19.032,19.090,22.237,24.350,22.274,20.193,16.615,19.575,20.391,16.459,20.527,21.438,19.327,21.424,16.656,17.616,18.416,16.666,19.961,18.120,18.023,18.588,21.759,17.841,19.548,18.486,21.391,15.656,16.634,16.377,14.246,17.451,15.742,18.038,18.470,14.936,17.987,15.751,19.750,16.202,14.733,V,,,20040418203500,20040419003500\n\
+    400,1,20,F14,76,\n\
+    400,21,24,A,,\n\
+    400,25,48,S14,1,\n\
+    200,CCCC123456,E1,001,B1,N1,METSER123,kWh,30,\n\
+    300,20040417,18.023,19.150,17.592,24.155,18.568,22.304,19.222,19.032,19.090,22.237,24.350,22.274,20.193,16.615,19.575,20.391,16.459,20.527,21.438,19.327,21.424,16.656,17.616,18.416,16.666,19.961,18.120,18.023,18.588,21.759,17.841,19.548,18.486,21.391,15.656,16.634,16.377,14.246,17.451,15.742,18.038,18.470,14.936,17.987,15.751,19.750,16.202,14.733,S14,,,20040418203500,20040419003500\n\
+    900\n";
+
+    #[test]
+    fn valid_methods() {
+        let nem12 = crate::nem12::parse(NEM12_SUBSTITUTED).unwrap();
+        assert_eq!(super::nem12(&nem12),vec![]);
+
+        let nem13 = crate::nem13::parse("100,NEM13,200505161145,CNRGYMDP,NEMMCO\n\
+        250,NEM1311002,11,1,11,11,11002,E,38841,20041117093206,S64,,,39013,20050217074053,E62,,,31,KWH,20050519,20050218104410,\n\
+        900\n").unwrap();
+        assert_eq!(super::nem13(&nem13),vec![]);
+    }
+
+    #[test]
+    fn installation_type_errors() {
+        let input = NEM12_SUBSTITUTED.replacen("400,25,48,S14","400,25,48,S64",1);
+        let nem12 = crate::nem12::parse(&input).unwrap();
+        let errors = super::nem12(&nem12);
+        assert_eq!(errors.len(),2);
+        assert!(matches!(&errors[0],ValidationError::InstallationType { nmi, format: Format::Nem12, .. } if nmi == "CCCC123456"));
+        assert_eq!(errors[1].to_string(),"NMI CCCC123456 mixes methods from several installation types: 14 64");
+
+        let input = NEM12_SUBSTITUTED.replacen(",S14,,,",",S52,,,",1);
+        let nem12 = crate::nem12::parse(&input).unwrap();
+        assert_eq!(super::nem12(&nem12),vec![ValidationError::MixedInstallationTypes {
+            nmi: "CCCC123456".into(),
+            methods: vec![MethodFlag::Flag14,MethodFlag::Flag52],
+        }]);
+
+        let nem13 = crate::nem13::parse("100,NEM13,200505161145,CNRGYMDP,NEMMCO\n\
+        250,NEM1311002,11,1,11,11,11002,E,38841,20041117093206,S14,,,39013,20050217074053,A,,,31,KWH,20050519,20050218104410,\n\
+        900\n").unwrap();
+        assert_eq!(super::nem13(&nem13)[0].to_string(),"NMI NEM1311002 on 2004-11-17: method S14 is not used for NEM13 installation types");
+    }
+
+    #[test]
+    fn method_for_quality_errors() {
+        let input = NEM12_SUBSTITUTED.replacen("400,1,20,F14","400,1,20,F54",1).replacen(",S14,,,",",E54,,,",1);
+        let nem12 = crate::nem12::parse(&input).unwrap();
+        let errors = super::nem12(&nem12);
+        assert_eq!(errors.iter().filter(|e| matches!(e,ValidationError::MethodForQuality { .. })).count(),1);
+        assert_eq!(errors[0].to_string(),"NMI CCCC123456 on 2004-04-17: method E54 is not an estimation method");
+
+        // The example puts a space before each update date and time, which only a lenient parse accepts.
+        let input = include_str!("../examples/unzipped/Example_NEM13_forward_estimate.csv");
+        let (nem13,_) = crate::nem13::parse_with(input, &crate::ParseOptions::lenient()).unwrap();
+        assert_eq!(super::nem13(&nem13).iter().map(|e| e.to_string()).collect::<Vec<_>>(),vec![
+            "NMI VDEF005890 on 2004-04-08: method E64 is not an estimation method",
+            "NMI VDEF005890 on 2004-04-08: method E64 is not an estimation method",
+        ]);
+    }
+
+    #[test]
+    fn interval_event_errors() {
+        let input = NEM12_SUBSTITUTED.replacen("400,21,24,A,,","400,21,23,A,,",1);
+        let nem12 = crate::nem12::parse(&input).unwrap();
+        assert_eq!(super::nem12(&nem12).iter().map(|e| e.to_string()).collect::<Vec<_>>(),vec![
+            "NMI CCCC123456 on 2004-04-17: interval 24 is not covered by any 400 record",
+        ]);
+
+        let input = NEM12_SUBSTITUTED.replacen(",V,,,",",A,,,",1);
+        let nem12 = crate::nem12::parse(&input).unwrap();
+        assert!(matches!(&super::nem12(&nem12)[..],[ValidationError::IntervalEvents { error: IntervalEventError::EventsWithoutVariableQuality, .. }]));
+    }
+}
@@ -0,0 +1,405 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use std::io::{self, Write};
+
+use crate::common::{MAX_DECIMAL_PLACES, MAX_NUMERIC_LENGTH};
+use crate::nem12::{
+    NEM12,
+    record::{B2BDetails, Header, IntervalData, IntervalEvent, NMIDataDetails, INTERVAL_LENGTHS, MINUTES_PER_DAY},
+};
+
+/// Writes NEM12 records to any `Write`, starting with the 100 header record.
+///
+/// Records are written in the order they are given, so a 200 record must be
+/// followed by its own 300, 400 and 500 records. [`Nem12Writer::finish`]
+/// writes the 900 end of data record.
+pub struct Nem12Writer<W> {
+    writer: W,
+    interval_length: Option<usize>,
+}
+
+impl <W: Write>Nem12Writer<W> {
+    pub fn new(mut writer: W, header: &Header) -> io::Result<Self> {
+        writeln!(
+            writer,
+            "100,{},{},{},{}",
+            header.format(),
+            header.created().format("%Y%m%d%H%M"),
+            header.from_participant(),
+            header.to_participant(),
+        )?;
+
+        Ok(Nem12Writer { writer, interval_length: None })
+    }
+
+    /// Writes a 200 record followed by all of its 300, 400 and 500 records.
+    pub fn write_nmi_data_details(&mut self, details: &NMIDataDetails) -> io::Result<()> {
+        if !INTERVAL_LENGTHS.contains(&details.interval_length) {
+            return Err(invalid_input("interval length must be 1, 5, 10, 15 or 30 minutes"));
+        }
+
+        write!(
+            self.writer,
+            "200,{},{},{},{},{},{},{},{:02},",
+            details.nmi,
+            details.nmi_configuration,
+            details.register_id.as_deref().unwrap_or(""),
+            details.nmi_suffix,
+            details.mdm_data_stream_id.as_deref().unwrap_or(""),
+            details.meter_serial_number,
+            details.uom,
+            details.interval_length,
+        )?;
+        write_date(&mut self.writer, details.next_scheduled_read_date)?;
+        writeln!(self.writer)?;
+        self.interval_length = Some(details.interval_length);
+
+        for interval_data in details.interval_data_vec.iter().flatten() {
+            self.write_interval_data(interval_data)?;
+        }
+        for b2b_details in details.b2b_details.iter().flatten() {
+            self.write_b2b_details(b2b_details)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a 300 record followed by its 400 and 500 records. The number of
+    /// values must match the interval length of the last 200 record written.
+    pub fn write_interval_data(&mut self, interval_data: &IntervalData) -> io::Result<()> {
+        let interval_length = self.interval_length
+            .ok_or_else(|| invalid_input("300 record before any 200 record"))?;
+        if interval_data.interval_value.len() * interval_length != MINUTES_PER_DAY {
+            return Err(invalid_input("number of interval values doesn't match the interval length"));
+        }
+        let update_datetime = interval_data.update_datetime
+            .ok_or_else(|| invalid_input("300 record without an update date and time"))?;
+
+        write!(self.writer, "300,{}", interval_data.interval_date.format("%Y%m%d"))?;
+        for value in &interval_data.interval_value {
+            write!(self.writer, ",{}", interval_value(*value)?)?;
+        }
+        write!(
+            self.writer,
+            ",{},{},{},{},",
+            interval_data.quality_method,
+            interval_data.reason_code.as_deref().unwrap_or(""),
+            interval_data.reason_description.as_deref().unwrap_or(""),
+            update_datetime.format("%Y%m%d%H%M%S"),
+        )?;
+        write_datetime(&mut self.writer, interval_data.msats_load_datetime)?;
+        writeln!(self.writer)?;
+
+        for interval_event in interval_data.interval_events.iter().flatten() {
+            self.write_interval_event(interval_event)?;
+        }
+        for b2b_details in interval_data.b2b_details.iter().flatten() {
+            self.write_b2b_details(b2b_details)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_interval_event(&mut self, interval_event: &IntervalEvent) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "400,{},{},{},{},{}",
+            interval_event.start_interval,
+            interval_event.end_interval,
+            interval_event.quality_method,
+            interval_event.reason_code.as_deref().unwrap_or(""),
+            interval_event.reason_description.as_deref().unwrap_or(""),
+        )
+    }
+
+    pub fn write_b2b_details(&mut self, b2b_details: &B2BDetails) -> io::Result<()> {
+        write!(
+            self.writer,
+            "500,{},{},",
+            b2b_details.trans_code,
+            b2b_details.ret_service_order.as_deref().unwrap_or(""),
+        )?;
+        write_datetime(&mut self.writer, b2b_details.read_datetime)?;
+        writeln!(self.writer, ",{}", b2b_details.index_read.as_deref().unwrap_or(""))
+    }
+
+    /// Writes the 900 end of data record and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        writeln!(self.writer, "900")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Writes a whole NEM12 file.
+pub fn write<W: Write>(writer: W, nem12: &NEM12) -> io::Result<W> {
+    let mut writer = Nem12Writer::new(writer, nem12.header())?;
+    for details in nem12.nmi_data_details() {
+        writer.write_nmi_data_details(details)?;
+    }
+    writer.finish()
+}
+
+fn write_date<W: Write>(writer: &mut W, date: Option<NaiveDate>) -> io::Result<()> {
+    match date {
+        Some(date) => write!(writer, "{}", date.format("%Y%m%d")),
+        None => Ok(()),
+    }
+}
+
+fn write_datetime<W: Write>(writer: &mut W, datetime: Option<NaiveDateTime>) -> io::Result<()> {
+    match datetime {
+        Some(datetime) => write!(writer, "{}", datetime.format("%Y%m%d%H%M%S")),
+        None => Ok(()),
+    }
+}
+
+/// Formats an interval value rounded to the 5 decimal places the specification
+/// allows, failing if it still doesn't fit in 15 characters.
+fn interval_value(value: f64) -> io::Result<String> {
+    if !value.is_finite() {
+        return Err(invalid_input("interval values must be finite"));
+    }
+    let scale = 10f64.powi(MAX_DECIMAL_PLACES as i32);
+    // Adding zero turns a value rounded to -0 into 0.
+    let rounded = (value * scale).round() / scale + 0.0;
+    let text = rounded.to_string();
+    if !rounded.is_finite() || text.len() > MAX_NUMERIC_LENGTH {
+        return Err(invalid_input("interval values must fit in 15 characters"));
+    }
+    Ok(text)
+}
+
+fn invalid_input(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nem12::record::Aggregation;
+    use crate::WarningKind;
+
+    /// Drops the leading and trailing zeros the writer leaves out, so `00.100`
+    /// becomes `0.1`, leaving every other digit as it was.
+    fn significant_digits(value: &str) -> String {
+        let (sign,value) = value.strip_prefix('-').map_or(("",value),|value| ("-",value));
+        let (integer,fraction) = value.split_once('.').unwrap_or((value,""));
+        let integer = integer.trim_start_matches('0');
+        let fraction = fraction.trim_end_matches('0');
+        match (integer,fraction) {
+            ("","") => "0".to_owned(),
+            (integer,"") => format!("{}{}",sign,integer),
+            ("",fraction) => format!("{}0.{}",sign,fraction),
+            (integer,fraction) => format!("{}{}.{}",sign,integer,fraction),
+        }
+    }
+
+    /// Writes the leniently parsed input back out, which must give the same
+    /// lines as the input. Interval values must keep every significant digit,
+    /// though `0.100` is written as `0.1`, and extra commas at the end of a
+    /// record are left out. Deviations from the specification that the writer
+    /// can't reproduce, such as missing trailing fields, fail.
+    fn round_trip(name: &str, input: &str) {
+        let (nem12,warnings) = crate::nem12::parse_with(input, &crate::ParseOptions::lenient()).unwrap_or_else(|e| panic!("{}: {}",name,e));
+        let mut extra_commas = Vec::new();
+        for warning in warnings {
+            match warning.kind {
+                WarningKind::ByteOrderMark | WarningKind::BlankLine | WarningKind::MixedLineEndings | WarningKind::UomCase => {}
+                WarningKind::ExtraTrailingCommas => extra_commas.push(warning.line),
+                _ => panic!("{}: {}",name,warning),
+            }
+        }
+        let output = String::from_utf8(write(Vec::new(), &nem12).unwrap()).unwrap();
+
+        let expected = input.trim_start_matches('\u{feff}').lines().enumerate().filter(|(_,line)| !line.trim().is_empty()).collect::<Vec<_>>();
+        assert_eq!(output.lines().count(),expected.len(),"{}",name);
+        for (line,(i,mut expected)) in output.lines().zip(expected) {
+            if extra_commas.contains(&(i + 1)) {
+                let end = expected.match_indices(',').nth(line.matches(',').count()).map_or(expected.len(),|(end,_)| end);
+                assert!(expected[end..].bytes().all(|b| b == b','),"{}: {}",name,expected);
+                expected = &expected[..end];
+            }
+            if !line.starts_with("300,") {
+                assert_eq!(line,expected,"{}",name);
+                continue;
+            }
+            let fields = line.split(',').collect::<Vec<_>>();
+            let expected_fields = expected.split(',').collect::<Vec<_>>();
+            assert_eq!(fields.len(),expected_fields.len(),"{}: {}",name,line);
+            let values = 2..fields.len() - 5;
+            for (i,(field,expected_field)) in fields.iter().zip(&expected_fields).enumerate() {
+                if values.contains(&i) {
+                    assert_eq!(*field,significant_digits(expected_field),"{}: {}",name,line);
+                } else {
+                    assert_eq!(field,expected_field,"{}: {}",name,line);
+                }
+            }
+        }
+        assert_eq!(crate::nem12::parse(&output).unwrap(),nem12);
+    }
+
+    #[test]
+    fn keeps_significant_digits() {
+        assert_eq!(significant_digits("19.150"),"19.15");
+        assert_eq!(significant_digits("001123.5"),"1123.5");
+        assert_eq!(significant_digits(".25"),"0.25");
+        assert_eq!(significant_digits("0.000"),"0");
+        assert_eq!(significant_digits("-0.50"),"-0.5");
+        assert_eq!(significant_digits("100"),"100");
+    }
+
+    /// Unzipped examples that stray too far from the specification to be
+    /// parsed and written back out.
+    const UNPARSEABLE_EXAMPLES: [(&str,&str); 3] = [
+        ("Example_NEM12_no_scheduled_read.csv","from participant longer than 10 characters"),
+        ("Example_NEM12_powercor.csv","no 100 header record"),
+        ("Example_NEM12_powercor_missing_fields.csv","no 100 header record"),
+    ];
+
+    /// Every unzipped NEM12 example must survive a round trip, apart from
+    /// those in [`UNPARSEABLE_EXAMPLES`], which must still fail to parse even
+    /// leniently.
+    #[test]
+    fn round_trips() {
+        let mut unparseable = Vec::new();
+        for entry in std::fs::read_dir("examples/unzipped").unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+            if !name.starts_with("Example_NEM12_") {
+                continue;
+            }
+            let contents = std::fs::read_to_string(&path).unwrap();
+            if UNPARSEABLE_EXAMPLES.iter().any(|(unparseable,_)| *unparseable == name) {
+                assert!(crate::nem12::parse_with(&contents, &crate::ParseOptions::lenient()).is_err(),"{} now parses",name);
+                unparseable.push(name);
+            } else {
+                round_trip(&name, &contents);
+            }
+        }
+        unparseable.sort();
+        assert_eq!(unparseable,UNPARSEABLE_EXAMPLES.map(|(name,_)| name.to_owned()));
+    }
+
+    /// Example archive entries that stray too far from the specification to
+    /// be parsed and written back out.
+    #[cfg(feature = "zip")]
+    const UNPARSEABLE_ENTRIES: [(&str,&str); 2] = [
+        ("Example_NEM12_powercor.csv","no 100 header record"),
+        ("NEM12#Scenario10#ETSAMDP#NEMMCO.csv","300 record without interval values"),
+    ];
+
+    /// Every example archive entry must survive a round trip, apart from those
+    /// in [`UNPARSEABLE_ENTRIES`], which must still fail to parse even
+    /// leniently.
+    #[cfg(feature = "zip")]
+    #[test]
+    fn round_trips_archives() {
+        let mut unparseable = Vec::new();
+        for entry in std::fs::read_dir("examples/nem12").unwrap() {
+            let archive = crate::archive::Archive::open(entry.unwrap().path()).unwrap();
+            for entry in archive.entries() {
+                if UNPARSEABLE_ENTRIES.iter().any(|(name,_)| *name == entry.name()) {
+                    assert!(crate::nem12::parse_with(entry.contents(), &crate::ParseOptions::lenient()).is_err(),"{} now parses",entry.name());
+                    unparseable.push(entry.name().to_owned());
+                } else {
+                    round_trip(entry.name(), entry.contents());
+                }
+            }
+        }
+        unparseable.sort();
+        let mut expected = UNPARSEABLE_ENTRIES.map(|(name,_)| name.to_owned()).to_vec();
+        expected.sort();
+        assert_eq!(unparseable,expected);
+    }
+
+    #[test]
+    fn writes_spec_formats() {
+        let input = "100,NEM12,200405011135,MDA1,Ret1\n\
+        200,VABD000163,E1Q1,1,E1,N1,METSER123,kWh,30,20040501\n\
+        300,20040201,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,0,V,,,20040202120025,\n\
+        400,1,20,F14,76,\n\
+        400,21,48,A,,\n\
+        500,S,RETNSRVCEORD1,20031220154500,001123.5\n\
+        900\n";
+        let nem12 = crate::nem12::parse(input).unwrap();
+        assert_eq!(String::from_utf8(write(Vec::new(), &nem12).unwrap()).unwrap(), input);
+    }
+
+    #[test]
+    fn writes_records_in_order() {
+        let input = "100,NEM12,200405011135,MDA1,Ret1\n\
+        200,VABD000163,E1Q1,1,E1,N1,METSER123,kWh,30,20040501\n\
+        300,20040201,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,0,V,,,20040202120025,\n\
+        400,1,20,F14,76,\n\
+        400,21,48,A,,\n\
+        500,S,RETNSRVCEORD1,20031220154500,001123.5\n\
+        300,20040202,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,1.111,0,A,,,20040203120025,\n\
+        500,E,RETNSRVCEORD2,20031221154500,001124.5\n\
+        900\n";
+        let nem12 = crate::nem12::parse(input).unwrap();
+        assert_eq!(String::from_utf8(write(Vec::new(), &nem12).unwrap()).unwrap(), input);
+    }
+
+    #[test]
+    fn writes_values_to_spec_precision() {
+        assert_eq!(interval_value(0.1 + 0.2).unwrap(),"0.3");
+        assert_eq!(interval_value(1.234565e-3).unwrap(),"0.00123");
+        assert_eq!(interval_value(-0.000001).unwrap(),"0");
+        assert_eq!(interval_value(999999999.99999).unwrap(),"999999999.99999");
+        assert_eq!(interval_value(1e15).unwrap_err().kind(),io::ErrorKind::InvalidInput);
+        assert_eq!(interval_value(f64::NAN).unwrap_err().kind(),io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn round_trips_resampled() {
+        let input = include_str!("../examples/unzipped/Example_NEM12_5_minute_interval.csv");
+        let nem12 = crate::nem12::parse(input).unwrap();
+
+        let mut resampled = Vec::new();
+        for details in nem12.nmi_data_details() {
+            let mut details = details.clone();
+            for interval_data in details.interval_data_vec.iter_mut().flatten() {
+                let intervals = interval_data.resample(30, Aggregation::Sum).unwrap();
+                interval_data.interval_value = intervals.iter().map(|interval| interval.value).collect();
+                interval_data.interval_events = None;
+            }
+            details.interval_length = 30;
+            resampled.push(details);
+        }
+        let nem12 = NEM12::new(nem12.header().clone(), resampled);
+
+        let output = String::from_utf8(write(Vec::new(), &nem12).unwrap()).unwrap();
+        let parsed = crate::nem12::parse(&output).unwrap();
+        for (details,expected) in parsed.nmi_data_details().iter().zip(nem12.nmi_data_details()) {
+            for (interval_data,expected) in details.interval_data_vec.iter().flatten().zip(expected.interval_data_vec.iter().flatten()) {
+                assert_eq!(interval_data.interval_value.len(),48);
+                for (value,expected) in interval_data.interval_value.iter().zip(&expected.interval_value) {
+                    assert!((value - expected).abs() < 1e-9,"{} {}",value,expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_records() {
+        let input = include_str!("../examples/unzipped/Example_NEM12_5_minute_interval.csv");
+        let nem12 = crate::nem12::parse(input).unwrap();
+        let mut details = nem12.nmi_data_details()[0].clone();
+
+        let mut writer = Nem12Writer::new(Vec::new(), nem12.header()).unwrap();
+        details.interval_length = 30;
+        assert_eq!(writer.write_nmi_data_details(&details).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        details.interval_length = 45;
+        assert_eq!(writer.write_nmi_data_details(&details).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let mut writer = Nem12Writer::new(Vec::new(), nem12.header()).unwrap();
+        let interval_data = &nem12.nmi_data_details()[0].interval_data_vec.as_ref().unwrap()[0];
+        assert!(writer.write_interval_data(interval_data).is_err());
+
+        let mut writer = Nem12Writer::new(Vec::new(), nem12.header()).unwrap();
+        let mut details = nem12.nmi_data_details()[0].clone();
+        details.interval_data_vec.as_mut().unwrap()[0].update_datetime = None;
+        assert_eq!(writer.write_nmi_data_details(&details).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}